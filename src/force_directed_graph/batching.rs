@@ -0,0 +1,545 @@
+use bevy::{
+    app::{App, Plugin, PreUpdate, Startup, Update},
+    asset::{load_internal_asset, Asset, Assets, Handle, RenderAssetUsages},
+    color::{ColorToComponents as _, LinearRgba},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventWriter,
+        query::{Added, With},
+        schedule::IntoSystemConfigs as _,
+        system::{Commands, Query, Res, ResMut},
+    },
+    math::Vec2,
+    picking::{
+        backend::{ray::RayMap, HitData, PointerHits},
+        PickSet, PickingBehavior,
+    },
+    reflect::TypePath,
+    render::{
+        camera::Camera,
+        mesh::{Indices, Mesh, Mesh2d, PrimitiveTopology},
+        render_resource::{AsBindGroup, Shader, ShaderRef},
+        view::{NoFrustumCulling, Visibility},
+    },
+    sprite::{AlphaMode2d, ColorMaterial, Material2d, Material2dPlugin, MeshMaterial2d},
+    transform::components::Transform,
+};
+
+use super::{
+    common::{LinkColor, LinkStyle, NodeColor, NodeLink, NodePhysics, NodeRadius},
+    lod::LodLevel,
+    node_shape::{self, ArrowheadSettings, NodeShape},
+    SimulationSet,
+};
+
+const NODE_SDF_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x6a1d_3b0f_52c4_4e9b);
+
+/// Material of the batched nodes: every quad is drawn as a circle in the
+/// quad's vertex color.
+#[derive(Asset, AsBindGroup, TypePath, Debug, Clone, Default)]
+pub struct NodeSdfMaterial {}
+
+impl Material2d for NodeSdfMaterial {
+    fn fragment_shader() -> ShaderRef {
+        NODE_SDF_SHADER_HANDLE.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Blend
+    }
+}
+
+/// Marker component for nodes which are drawn by the [NodeBatch] instead of
+/// their own mesh.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchedNode;
+
+/// Marker component for the entity whose mesh contains all [BatchedNode]s.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeBatch;
+
+/// Marker component for links which are drawn by the [LinkBatch] instead of
+/// their own mesh. Their [Transform] is not updated.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchedLink;
+
+/// Marker component for the entity whose mesh contains all [BatchedLink]s.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkBatch;
+
+/// Plugin for drawing the graph with few draw calls:
+///
+/// * `nodes` - all circular nodes are quads of one shared mesh, which is
+///   rebuilt every frame from the nodes' [Transform], [NodeRadius] and
+///   [NodeColor]. The circle is cut out by a shader (signed distance
+///   field). Nodes with another [NodeShape] keep their own mesh.
+/// * `links` - all links are quads of one shared mesh with vertex colors,
+///   rebuilt every frame from the node positions, [LinkStyle]s and
+///   [LinkColor]s. The [NodeLink] entities remain as data, without a mesh or
+///   material.
+///
+/// Batched nodes and links don't have a mesh of their own, so they are picked
+/// by distance instead.
+pub struct BatchedRenderPlugin {
+    pub nodes: bool,
+    pub links: bool,
+}
+
+impl Default for BatchedRenderPlugin {
+    fn default() -> Self {
+        Self {
+            nodes: true,
+            links: true,
+        }
+    }
+}
+
+impl Plugin for BatchedRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreUpdate, pick_batched.in_set(PickSet::Backend));
+        if self.nodes {
+            load_internal_asset!(
+                app,
+                NODE_SDF_SHADER_HANDLE,
+                "node_sdf.wgsl",
+                Shader::from_wgsl
+            );
+            app.add_plugins(Material2dPlugin::<NodeSdfMaterial>::default())
+                .add_systems(Startup, setup_node_batch)
+                .add_systems(
+                    Update,
+                    (batch_nodes, update_node_batch)
+                        .chain()
+                        .after(SimulationSet::Constraints),
+                );
+        }
+        if self.links {
+            app.add_systems(Startup, setup_link_batch).add_systems(
+                Update,
+                (batch_links, update_link_batch)
+                    .chain()
+                    .after(SimulationSet::Constraints),
+            );
+        }
+    }
+}
+
+pub fn setup_node_batch(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<NodeSdfMaterial>>,
+) {
+    commands.spawn((
+        NodeBatch,
+        Mesh2d(meshes.add(quads_mesh(&[]))),
+        MeshMaterial2d(materials.add(NodeSdfMaterial::default())),
+        Transform::default(),
+        // The bounds change every frame
+        NoFrustumCulling,
+        PickingBehavior::IGNORE,
+    ));
+}
+
+/// Remove the mesh of new circular nodes, they are drawn by the [NodeBatch]
+/// in their [NodeColor]. Thus, they don't get a material either.
+pub fn batch_nodes(
+    nodes_q: Query<(Entity, Option<&NodeShape>), (Added<Mesh2d>, With<NodePhysics>)>,
+    mut commands: Commands,
+) {
+    for (entity, shape) in &nodes_q {
+        if matches!(shape, None | Some(NodeShape::Circle)) {
+            commands
+                .entity(entity)
+                .remove::<Mesh2d>()
+                .insert(BatchedNode);
+        }
+    }
+}
+
+/// Rebuild the mesh of the [NodeBatch] from the batched nodes.
+pub fn update_node_batch(
+    nodes_q: Query<
+        (
+            &Transform,
+            Option<&NodeRadius>,
+            Option<&NodeColor>,
+            Option<&Visibility>,
+        ),
+        (With<BatchedNode>, With<NodePhysics>),
+    >,
+    batch_q: Query<&Mesh2d, With<NodeBatch>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let Some(mesh) = batch_q
+        .get_single()
+        .ok()
+        .and_then(|mesh| meshes.get_mut(&mesh.0))
+    else {
+        return;
+    };
+    let quads = nodes_q
+        .iter()
+        .filter(|(.., visibility)| visibility != &Some(&Visibility::Hidden))
+        .map(|(transform, radius, color, _)| Quad {
+            center: transform.translation.truncate(),
+            z: transform.translation.z,
+            half_size: radius.copied().unwrap_or_default().0,
+            color: color.map_or(LinearRgba::WHITE, |color| color.0.to_linear()),
+        })
+        .collect::<Vec<_>>();
+    *mesh = quads_mesh(&quads);
+}
+
+pub fn setup_link_batch(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.spawn((
+        LinkBatch,
+        Mesh2d(meshes.add(segments_mesh(&[]))),
+        // Multiplied by the vertex colors
+        MeshMaterial2d(materials.add(ColorMaterial::default())),
+        // Behind the nodes
+        Transform::from_xyz(0.0, 0.0, -1.0),
+        NoFrustumCulling,
+        PickingBehavior::IGNORE,
+    ));
+}
+
+/// Remove the mesh of new links, they are drawn by the [LinkBatch] in their
+/// [LinkColor]. Thus, they don't get a material either.
+pub fn batch_links(
+    links_q: Query<Entity, (Added<Mesh2d>, With<NodeLink>)>,
+    mut commands: Commands,
+) {
+    for entity in &links_q {
+        commands
+            .entity(entity)
+            .remove::<Mesh2d>()
+            .insert(BatchedLink);
+    }
+}
+
+/// Rebuild the mesh of the [LinkBatch] from the batched links. Like
+/// [super::update_links], links end at the boundary of the nodes' shapes. Their
+/// arrowheads are part of the mesh as well, instead of [node_shape::Arrowhead]
+/// entities.
+pub fn update_link_batch(
+    links_q: Query<
+        (
+            &NodeLink,
+            Option<&LinkStyle>,
+            Option<&LinkColor>,
+            Option<&Visibility>,
+        ),
+        With<BatchedLink>,
+    >,
+    nodes_q: Query<(&Transform, Option<&NodeShape>, Option<&NodeRadius>), With<NodePhysics>>,
+    batch_q: Query<&Mesh2d, With<LinkBatch>>,
+    lod_level: Option<Res<LodLevel>>,
+    arrowhead_settings: Option<Res<ArrowheadSettings>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let arrowhead_size = arrowhead_settings
+        .filter(|settings| settings.enabled)
+        .filter(|_| {
+            !lod_level
+                .as_ref()
+                .is_some_and(|level| level.arrowheads_hidden)
+        })
+        .map(|settings| settings.size);
+    let Some(mesh) = batch_q
+        .get_single()
+        .ok()
+        .and_then(|mesh| meshes.get_mut(&mesh.0))
+    else {
+        return;
+    };
+    let segments = links_q
+        .iter()
+        .filter(|(.., visibility)| visibility != &Some(&Visibility::Hidden))
+        .filter_map(|(link, style, color, _)| {
+            let (source, source_shape, source_radius) = nodes_q.get(link.source).ok()?;
+            let (target, target_shape, target_radius) = nodes_q.get(link.target).ok()?;
+            let (start, end) = node_shape::clipped_endpoints(
+                source.translation.truncate(),
+                (source_shape, source_radius),
+                target.translation.truncate(),
+                (target_shape, target_radius),
+            );
+            let style = style.copied().unwrap_or_default();
+            let color = color.map_or(style.color, |color| color.0);
+            // Sized like the arrowhead entities, see [node_shape::update_arrowheads]
+            let arrowhead = arrowhead_size
+                .map(|size| size + style.thickness)
+                .filter(|&size| start.distance(end) >= size);
+            Some(Segment {
+                start,
+                end,
+                thickness: lod_level.as_deref().map_or(style.thickness, |level| {
+                    level.link_thickness(style.thickness)
+                }),
+                color: color.to_linear(),
+                arrowhead,
+            })
+        })
+        .collect::<Vec<_>>();
+    *mesh = segments_mesh(&segments);
+}
+
+/// Send pointer hits for batched nodes and links under the pointer.
+pub fn pick_batched(
+    ray_map: Res<RayMap>,
+    cameras_q: Query<&Camera>,
+    nodes_q: Query<
+        (Entity, &Transform, Option<&NodeRadius>),
+        (With<BatchedNode>, With<NodePhysics>),
+    >,
+    links_q: Query<(Entity, &NodeLink, Option<&LinkStyle>), With<BatchedLink>>,
+    transforms_q: Query<&Transform, With<NodePhysics>>,
+    mut hits: EventWriter<PointerHits>,
+) {
+    for (ray_id, ray) in ray_map.map() {
+        let Ok(camera) = cameras_q.get(ray_id.camera) else {
+            continue;
+        };
+        let position = ray.origin.truncate();
+        let node_picks = nodes_q
+            .iter()
+            .filter(|(_, transform, radius)| {
+                let radius = radius.copied().unwrap_or_default().0;
+                transform.translation.truncate().distance_squared(position) <= radius * radius
+            })
+            .map(|(entity, transform, _)| {
+                let depth = ray.origin.z - transform.translation.z;
+                let hit = HitData::new(ray_id.camera, depth, Some(transform.translation), None);
+                (entity, hit)
+            });
+        // Links are behind all nodes
+        let link_picks = links_q.iter().filter_map(|(entity, link, style)| {
+            let source = transforms_q.get(link.source).ok()?.translation.truncate();
+            let target = transforms_q.get(link.target).ok()?.translation.truncate();
+            let thickness = style.copied().unwrap_or_default().thickness;
+            // Links are hard to hit if they are thin
+            let tolerance = thickness.max(4.0) / 2.0;
+            (segment_distance(position, source, target) <= tolerance).then(|| {
+                let hit = HitData::new(ray_id.camera, ray.origin.z + 1.0, None, None);
+                (entity, hit)
+            })
+        });
+        let picks = node_picks.chain(link_picks).collect::<Vec<_>>();
+        if !picks.is_empty() {
+            hits.send(PointerHits::new(ray_id.pointer, picks, camera.order as f32));
+        }
+    }
+}
+
+/// Square with its center, half size and vertex color.
+pub struct Quad {
+    pub center: Vec2,
+    pub z: f32,
+    pub half_size: f32,
+    pub color: LinearRgba,
+}
+
+/// Line with its end points, thickness and vertex color.
+pub struct Segment {
+    pub start: Vec2,
+    pub end: Vec2,
+    pub thickness: f32,
+    pub color: LinearRgba,
+    /// Length of the arrowhead at the end, if any
+    pub arrowhead: Option<f32>,
+}
+
+/// Distance from `point` to the line segment between `start` and `end`.
+fn segment_distance(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let direction = end - start;
+    let t = (point - start).dot(direction) / direction.length_squared().max(f32::EPSILON);
+    point.distance(start + direction * t.clamp(0.0, 1.0))
+}
+
+/// Mesh with one quad per segment, plus a triangle per arrowhead.
+pub fn segments_mesh(segments: &[Segment]) -> Mesh {
+    let mut positions = Vec::with_capacity(7 * segments.len());
+    let mut colors = Vec::with_capacity(7 * segments.len());
+    let mut indices = Vec::with_capacity(9 * segments.len());
+    for segment in segments {
+        let start = positions.len() as u32;
+        let direction = (segment.end - segment.start).normalize_or_zero();
+        let normal = direction.perp() * segment.thickness / 2.0;
+        for position in [
+            segment.start - normal,
+            segment.end - normal,
+            segment.end + normal,
+            segment.start + normal,
+        ] {
+            positions.push([position.x, position.y, 0.0]);
+            colors.push(segment.color.to_f32_array());
+        }
+        indices.extend([start, start + 1, start + 2, start, start + 2, start + 3]);
+
+        if let Some(size) = segment.arrowhead {
+            // Tip at the end, like the mesh of [node_shape::ArrowheadAssets]
+            let base = segment.end - direction * size;
+            let half_width = direction.perp() * size / 2.0;
+            for position in [segment.end, base + half_width, base - half_width] {
+                positions.push([position.x, position.y, 0.0]);
+                colors.push(segment.color.to_f32_array());
+            }
+            indices.extend([start + 4, start + 5, start + 6]);
+        }
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Mesh of quads with UVs spanning `[0, 1]` per quad.
+pub fn quads_mesh(quads: &[Quad]) -> Mesh {
+    let mut positions = Vec::with_capacity(4 * quads.len());
+    let mut uvs = Vec::with_capacity(4 * quads.len());
+    let mut colors = Vec::with_capacity(4 * quads.len());
+    let mut indices = Vec::with_capacity(6 * quads.len());
+    for quad in quads {
+        let start = positions.len() as u32;
+        for corner in [
+            Vec2::new(-1.0, -1.0),
+            Vec2::new(1.0, -1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(-1.0, 1.0),
+        ] {
+            let position = quad.center + corner * quad.half_size;
+            positions.push([position.x, position.y, quad.z]);
+            uvs.push([(corner.x + 1.0) / 2.0, (1.0 - corner.y) / 2.0]);
+            colors.push(quad.color.to_f32_array());
+        }
+        indices.extend([start, start + 1, start + 2, start, start + 2, start + 3]);
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        batch_links, quads_mesh, segment_distance, segments_mesh, BatchedLink, Quad, Segment,
+    };
+    use crate::force_directed_graph::{
+        apply_link_colors,
+        common::{LinkColor, NodeLink},
+    };
+    use bevy::{
+        app::{App, PostUpdate, Update},
+        asset::{Assets, Handle},
+        color::{Color, LinearRgba},
+        ecs::entity::Entity,
+        math::Vec2,
+        render::mesh::{Mesh, Mesh2d},
+        sprite::{ColorMaterial, MeshMaterial2d},
+    };
+
+    #[test]
+    fn test_quads_mesh() {
+        let quad = |x: f32| Quad {
+            center: Vec2::new(x, 0.0),
+            z: 0.0,
+            half_size: 5.0,
+            color: LinearRgba::RED,
+        };
+        let mesh = quads_mesh(&[quad(0.0), quad(100.0)]);
+        assert_eq!(mesh.count_vertices(), 8);
+        assert_eq!(mesh.indices().unwrap().len(), 12);
+        let positions = mesh.attribute(Mesh::ATTRIBUTE_POSITION).unwrap();
+        assert_eq!(
+            positions.as_float3().unwrap()[4..6],
+            [[95.0, -5.0, 0.0], [105.0, -5.0, 0.0]]
+        );
+        assert!(quads_mesh(&[]).indices().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_segments_mesh() {
+        let mesh = segments_mesh(&[Segment {
+            start: Vec2::ZERO,
+            end: Vec2::new(10.0, 0.0),
+            thickness: 2.0,
+            color: LinearRgba::RED,
+            arrowhead: None,
+        }]);
+        let positions = mesh.attribute(Mesh::ATTRIBUTE_POSITION).unwrap();
+        assert_eq!(
+            positions.as_float3().unwrap(),
+            [
+                [0.0, -1.0, 0.0],
+                [10.0, -1.0, 0.0],
+                [10.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0]
+            ]
+        );
+
+        let mesh = segments_mesh(&[Segment {
+            start: Vec2::ZERO,
+            end: Vec2::new(10.0, 0.0),
+            thickness: 2.0,
+            color: LinearRgba::RED,
+            arrowhead: Some(4.0),
+        }]);
+        assert_eq!(mesh.count_vertices(), 7);
+        assert_eq!(mesh.indices().unwrap().len(), 9);
+        let positions = mesh.attribute(Mesh::ATTRIBUTE_POSITION).unwrap();
+        assert_eq!(
+            positions.as_float3().unwrap()[4..],
+            [[10.0, 0.0, 0.0], [6.0, 2.0, 0.0], [6.0, -2.0, 0.0]]
+        );
+
+        let (start, end) = (Vec2::ZERO, Vec2::new(10.0, 0.0));
+        assert_eq!(segment_distance(Vec2::new(5.0, 3.0), start, end), 3.0);
+        assert_eq!(segment_distance(Vec2::new(-4.0, 3.0), start, end), 5.0);
+        assert_eq!(
+            segment_distance(Vec2::new(1.0, 1.0), start, start),
+            2f32.sqrt()
+        );
+    }
+
+    #[test]
+    fn test_batched_links_have_no_material() {
+        let mut app = App::new();
+        app.init_resource::<Assets<ColorMaterial>>()
+            .add_systems(Update, batch_links)
+            .add_systems(PostUpdate, apply_link_colors);
+        let link = app
+            .world_mut()
+            .spawn((
+                NodeLink {
+                    source: Entity::from_raw(0),
+                    target: Entity::from_raw(1),
+                    target_distance: 100.0,
+                },
+                LinkColor(Color::WHITE),
+                Mesh2d(Handle::<Mesh>::default()),
+            ))
+            .id();
+        app.update();
+
+        let link = app.world().entity(link);
+        assert!(link.contains::<BatchedLink>());
+        assert!(!link.contains::<Mesh2d>());
+        assert!(!link.contains::<MeshMaterial2d<ColorMaterial>>());
+        assert!(app.world().resource::<Assets<ColorMaterial>>().is_empty());
+    }
+}
@@ -1,13 +1,23 @@
 use bevy::{
-    ecs::{component::Component, entity::Entity},
+    color::Color,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::Added,
+        removal_detection::RemovedComponents,
+        system::{Query, SystemParam},
+    },
     math::Vec2,
     transform::components::Transform,
 };
 
+use super::utils::ClampF32Range as _;
+
 /// Component to save a nodes previous position. Instead of saving the velocity,
 /// we approximate the velocity via the difference between the current and
 /// previous position. This is needed for inertia.
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Clone)]
+#[require(ForceAccumulator)]
 pub struct NodePhysics {
     pub previous_position: Vec2,
 }
@@ -20,14 +30,127 @@ impl NodePhysics {
     }
 }
 
+/// Sum of the forces acting on a node in the current tick, in kg*px/s^2. Forces
+/// add into it instead of moving the node, so their order does not matter.
+/// [crate::force_directed_graph::verlet::apply_forces] moves the node by the
+/// sum and clears it.
+#[derive(Component, Debug, Clone, Default)]
+pub struct ForceAccumulator {
+    /// Force per label of the force, e.g. `"link"`
+    pub forces: Vec<(&'static str, Vec2)>,
+}
+
+impl ForceAccumulator {
+    pub fn add(&mut self, label: &'static str, force: Vec2) {
+        match self.forces.iter_mut().find(|(other, _)| *other == label) {
+            Some((_, sum)) => *sum = (*sum + force).clamp_f32_range(),
+            None => self.forces.push((label, force)),
+        }
+    }
+
+    /// Sum of all forces, each multiplied by its scale.
+    pub fn total(&self, scale: impl Fn(&str) -> f32) -> Vec2 {
+        self.forces
+            .iter()
+            .map(|(label, force)| (*force * scale(label)).clamp_f32_range())
+            .sum::<Vec2>()
+            .clamp_f32_range()
+    }
+
+    pub fn clear(&mut self) {
+        self.forces.clear();
+    }
+}
+
+/// Stable identity of a node. Unlike [Entity], it stays the same between
+/// sessions, e.g. for saving and restoring layouts.
+#[derive(Component, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NodeId(pub String);
+
+/// Human-readable name of a node, e.g. shown next to it.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct NodeLabel(pub String);
+
+/// Marker component for the text entity which shows a node's [NodeLabel].
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeLabelText;
+
+/// Domain data attached to a node as key/value pairs, e.g. shown in the
+/// inspector panel.
+#[derive(Component, Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeMetadata(pub Vec<(String, String)>);
+
+/// Radius of a node's circle in px.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct NodeRadius(pub f32);
+
+impl Default for NodeRadius {
+    fn default() -> Self {
+        Self(15.0)
+    }
+}
+
+/// Color of a node. Nodes with a mesh of their own get it applied to their
+/// material (see [crate::force_directed_graph::apply_node_colors]), batched
+/// nodes are colored by the
+/// [NodeBatch](crate::force_directed_graph::batching::NodeBatch) directly.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct NodeColor(pub Color);
+
+/// Component for nodes which are pinned to a fixed position. Forces and
+/// inertia do not move them (see
+/// [crate::force_directed_graph::forces::pinned::apply_pinned]), but they can
+/// still be dragged.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Pinned {
+    pub position: Vec2,
+}
+
 /// Component for saving which nodes a given link connects.
-#[derive(Component)]
+#[derive(Component, Debug, Clone)]
 pub struct NodeLink {
     pub source: Entity,
     pub target: Entity,
     pub target_distance: f32,
 }
 
+/// Strength of the relationship a link represents, e.g. the number of calls
+/// between two modules. See
+/// [crate::force_directed_graph::link_style::LinkStylePlugin].
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct LinkWeight(pub f32);
+
+impl Default for LinkWeight {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// How a link is drawn. Either set directly or derived from the [LinkWeight].
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct LinkStyle {
+    /// Thickness in px
+    pub thickness: f32,
+    pub color: Color,
+}
+
+impl Default for LinkStyle {
+    fn default() -> Self {
+        Self {
+            thickness: 2.5,
+            color: Color::srgba(1.0, 1.0, 1.0, 0.5),
+        }
+    }
+}
+
+/// Color a link is drawn in: the color of its [LinkStyle], possibly dimmed by
+/// the hover highlight. Links with a mesh of their own get it applied to their
+/// material (see [crate::force_directed_graph::apply_link_colors]), batched
+/// links are colored by the
+/// [LinkBatch](crate::force_directed_graph::batching::LinkBatch) directly.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct LinkColor(pub Color);
+
 // `MouseLocked` is a marker component for node entities which are currently
 // drag-and-dropped. This is needed for disabling forces and inertia
 // temporarily. Additionally, we save the velocity of the mouse movement to
@@ -36,3 +159,21 @@ pub struct NodeLink {
 pub struct MouseLocked {
     pub velocity: Vec2,
 }
+
+/// Nodes or links which were added or removed since the system using it ran
+/// last.
+#[derive(SystemParam)]
+pub struct GraphChanges<'w, 's> {
+    added_nodes_q: Query<'w, 's, (), Added<NodePhysics>>,
+    added_links_q: Query<'w, 's, (), Added<NodeLink>>,
+    removed_nodes: RemovedComponents<'w, 's, NodePhysics>,
+    removed_links: RemovedComponents<'w, 's, NodeLink>,
+}
+
+impl GraphChanges<'_, '_> {
+    /// Whether any node or link was added or removed.
+    pub fn any(&mut self) -> bool {
+        let removed = self.removed_nodes.read().count() + self.removed_links.read().count() > 0;
+        removed || !self.added_nodes_q.is_empty() || !self.added_links_q.is_empty()
+    }
+}
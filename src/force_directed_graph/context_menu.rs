@@ -0,0 +1,368 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    color::{Alpha as _, Color},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{Event, EventReader, EventWriter},
+        observer::Trigger,
+        query::{Changed, Has, With},
+        system::{Commands, Query, Res, ResMut, Resource, SystemParam},
+    },
+    hierarchy::{BuildChildren as _, ChildBuild as _, DespawnRecursiveExt as _},
+    input::{keyboard::KeyCode, mouse::MouseButton, ButtonInput},
+    log::warn,
+    picking::{
+        events::{Click, Pointer},
+        pointer::PointerButton,
+    },
+    text::{TextColor, TextFont},
+    transform::components::Transform,
+    ui::{
+        widget::{Button, Text},
+        BackgroundColor, FlexDirection, Interaction, Node, PositionType, UiRect, Val,
+    },
+    utils::default,
+};
+
+use super::{
+    common::{NodeId, NodeLink, NodePhysics, Pinned},
+    graph_store::GraphStore,
+    groups::MetaNode,
+    hidden::{HiddenLink, HiddenNode, HiddenPlugin, HideNode, ShowNode},
+    search::FocusNode,
+};
+
+const ITEM_COLOR: Color = Color::NONE;
+const ITEM_HOVERED_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.2);
+
+/// Action on a single node, e.g. chosen from the context menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeAction {
+    /// Pin the node at its current position, see [Pinned]
+    Pin,
+    Unpin,
+    /// Hide the node and its links, see [HideNode]
+    Hide,
+    /// Show all hidden neighbors
+    Expand,
+    /// Hide all neighbors
+    Collapse,
+    /// Despawn the node and its links and remove it from the [GraphStore], so
+    /// expanding a neighbor does not bring it back. Meta-nodes cannot be
+    /// removed, they need to be expanded first (see [MetaNode]).
+    Remove,
+    /// Center the camera on the node, see [FocusNode]
+    CenterView,
+    /// Action registered via [ContextMenuPlugin::custom_actions]. It is not
+    /// handled by this crate, but can be handled by reading [ApplyNodeAction]
+    /// events.
+    Custom(String),
+}
+
+impl NodeAction {
+    pub fn label(&self) -> &str {
+        match self {
+            NodeAction::Pin => "Pin",
+            NodeAction::Unpin => "Unpin",
+            NodeAction::Hide => "Hide",
+            NodeAction::Expand => "Expand neighbors",
+            NodeAction::Collapse => "Collapse neighbors",
+            NodeAction::Remove => "Remove",
+            NodeAction::CenterView => "Center view",
+            NodeAction::Custom(name) => name,
+        }
+    }
+}
+
+/// Event to apply an action to a node.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct ApplyNodeAction {
+    pub entity: Entity,
+    pub action: NodeAction,
+}
+
+/// Names of the custom actions which are listed in the context menu, see
+/// [NodeAction::Custom].
+#[derive(Resource, Debug, Clone, Default)]
+pub struct CustomNodeActions(pub Vec<String>);
+
+/// Marker component for the context menu.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ContextMenu;
+
+/// Component for the buttons within the context menu.
+#[derive(Component, Debug, Clone)]
+pub struct ContextMenuItem {
+    pub entity: Entity,
+    pub action: NodeAction,
+}
+
+/// Plugin for a context menu which opens when right-clicking a node (see
+/// [open_context_menu]). Choosing an entry sends an [ApplyNodeAction] event.
+/// The menu is closed by clicking anywhere else or pressing `Escape`.
+#[derive(Default)]
+pub struct ContextMenuPlugin {
+    /// Additional entries, see [NodeAction::Custom]
+    pub custom_actions: Vec<String>,
+}
+
+impl Plugin for ContextMenuPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<HiddenPlugin>() {
+            app.add_plugins(HiddenPlugin);
+        }
+        app.insert_resource(CustomNodeActions(self.custom_actions.clone()))
+            .add_event::<ApplyNodeAction>()
+            .add_event::<FocusNode>()
+            .add_systems(
+                Update,
+                (
+                    close_context_menu,
+                    context_menu_interaction,
+                    apply_node_actions,
+                ),
+            );
+    }
+}
+
+/// Shown and hidden neighbors of nodes, to decide which actions apply to a
+/// node.
+#[derive(SystemParam)]
+pub struct NodeNeighbors<'w, 's> {
+    links_q: Query<'w, 's, &'static NodeLink>,
+    hidden_links_q: Query<'w, 's, &'static HiddenLink>,
+    hidden_nodes_q: Query<'w, 's, (), With<HiddenNode>>,
+}
+
+impl NodeNeighbors<'_, '_> {
+    pub fn has_visible(&self, entity: Entity) -> bool {
+        self.links_q
+            .iter()
+            .any(|link| other_end(link, entity).is_some())
+    }
+
+    pub fn has_hidden(&self, entity: Entity) -> bool {
+        self.hidden_links_q.iter().any(|HiddenLink(link)| {
+            other_end(link, entity).is_some_and(|neighbor| self.hidden_nodes_q.contains(neighbor))
+        })
+    }
+}
+
+/// The node at the other end of `link`, if `entity` is one of its nodes.
+fn other_end(link: &NodeLink, entity: Entity) -> Option<Entity> {
+    if link.source == entity {
+        Some(link.target)
+    } else if link.target == entity {
+        Some(link.source)
+    } else {
+        None
+    }
+}
+
+/// Observer for click events on nodes. Opens the context menu on right-click.
+pub fn open_context_menu(
+    trigger: Trigger<Pointer<Click>>,
+    nodes_q: Query<(Has<Pinned>, Has<MetaNode>), With<NodePhysics>>,
+    neighbors: NodeNeighbors,
+    menus_q: Query<Entity, With<ContextMenu>>,
+    custom_actions: Res<CustomNodeActions>,
+    mut commands: Commands,
+) {
+    if trigger.event().button != PointerButton::Secondary {
+        return;
+    }
+    let entity = trigger.entity();
+    let Ok((pinned, meta_node)) = nodes_q.get(entity) else {
+        return;
+    };
+    for menu in &menus_q {
+        commands.entity(menu).despawn_recursive();
+    }
+
+    let mut actions = vec![if pinned {
+        NodeAction::Unpin
+    } else {
+        NodeAction::Pin
+    }];
+    actions.push(NodeAction::Hide);
+    if neighbors.has_hidden(entity) {
+        actions.push(NodeAction::Expand);
+    }
+    if neighbors.has_visible(entity) {
+        actions.push(NodeAction::Collapse);
+    }
+    if !meta_node {
+        actions.push(NodeAction::Remove);
+    }
+    actions.push(NodeAction::CenterView);
+    actions.extend(custom_actions.0.iter().cloned().map(NodeAction::Custom));
+
+    let position = trigger.event().pointer_location.position;
+    commands
+        .spawn((
+            ContextMenu,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(position.x),
+                top: Val::Px(position.y),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(4.0)),
+                ..default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.8)),
+        ))
+        .with_children(|parent| {
+            for action in actions {
+                parent
+                    .spawn((
+                        Button,
+                        Node {
+                            padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+                            ..default()
+                        },
+                        BackgroundColor(ITEM_COLOR),
+                    ))
+                    .with_child((
+                        Text::new(action.label()),
+                        TextFont {
+                            font_size: 12.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ))
+                    .insert(ContextMenuItem { entity, action });
+            }
+        });
+}
+
+/// Close the context menu on `Escape` or when clicking anywhere else.
+pub fn close_context_menu(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    items_q: Query<&Interaction, With<ContextMenuItem>>,
+    menus_q: Query<Entity, With<ContextMenu>>,
+    mut commands: Commands,
+) {
+    let on_menu = items_q
+        .iter()
+        .any(|interaction| *interaction != Interaction::None);
+    let clicked_elsewhere = mouse.get_just_pressed().next().is_some() && !on_menu;
+    if keyboard.just_pressed(KeyCode::Escape) || clicked_elsewhere {
+        for menu in &menus_q {
+            commands.entity(menu).despawn_recursive();
+        }
+    }
+}
+
+/// Highlight hovered menu entries and send an [ApplyNodeAction] event when an
+/// entry is pressed.
+pub fn context_menu_interaction(
+    mut items_q: Query<
+        (&Interaction, &ContextMenuItem, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    menus_q: Query<Entity, With<ContextMenu>>,
+    mut action_events: EventWriter<ApplyNodeAction>,
+    mut commands: Commands,
+) {
+    for (interaction, item, mut background_color) in &mut items_q {
+        match interaction {
+            Interaction::Pressed => {
+                action_events.send(ApplyNodeAction {
+                    entity: item.entity,
+                    action: item.action.clone(),
+                });
+                for menu in &menus_q {
+                    commands.entity(menu).despawn_recursive();
+                }
+            }
+            Interaction::Hovered => background_color.0 = ITEM_HOVERED_COLOR,
+            Interaction::None => background_color.0 = ITEM_COLOR,
+        }
+    }
+}
+
+/// Events sent by the built-in actions, see [apply_node_actions].
+#[derive(SystemParam)]
+pub struct NodeActionEvents<'w> {
+    hide: EventWriter<'w, HideNode>,
+    show: EventWriter<'w, ShowNode>,
+    focus: EventWriter<'w, FocusNode>,
+}
+
+/// Apply the built-in actions on [ApplyNodeAction] events. Custom actions are
+/// ignored.
+pub fn apply_node_actions(
+    mut action_events: EventReader<ApplyNodeAction>,
+    nodes_q: Query<(&Transform, Option<&NodeId>, Has<MetaNode>), With<NodePhysics>>,
+    links_q: Query<(Entity, &NodeLink)>,
+    hidden_links_q: Query<(Entity, &HiddenLink)>,
+    mut store: Option<ResMut<GraphStore>>,
+    mut events: NodeActionEvents,
+    mut commands: Commands,
+) {
+    for ApplyNodeAction { entity, action } in action_events.read() {
+        let entity = *entity;
+        let Ok((transform, id, meta_node)) = nodes_q.get(entity) else {
+            continue;
+        };
+        let neighbors = |links: &mut dyn Iterator<Item = &NodeLink>| {
+            links
+                .filter_map(|link| other_end(link, entity))
+                .collect::<Vec<_>>()
+        };
+
+        match action {
+            NodeAction::Pin => {
+                commands.entity(entity).insert(Pinned {
+                    position: transform.translation.truncate(),
+                });
+            }
+            NodeAction::Unpin => {
+                commands.entity(entity).remove::<Pinned>();
+            }
+            NodeAction::Hide => {
+                events.hide.send(HideNode(entity));
+            }
+            NodeAction::Expand => {
+                let hidden_links = &mut hidden_links_q.iter().map(|(_, HiddenLink(link))| link);
+                events
+                    .show
+                    .send_batch(neighbors(hidden_links).into_iter().map(ShowNode));
+            }
+            NodeAction::Collapse => {
+                let links = &mut links_q.iter().map(|(_, link)| link);
+                events
+                    .hide
+                    .send_batch(neighbors(links).into_iter().map(HideNode));
+            }
+            NodeAction::Remove if meta_node => {
+                // Its members would stay hidden forever
+                warn!("Cannot remove meta-node {}, expand it first", entity);
+            }
+            NodeAction::Remove => {
+                if let Some((store, id)) = store.as_deref_mut().zip(id) {
+                    store.remove_node(&id.0);
+                }
+                let links = links_q
+                    .iter()
+                    .chain(hidden_links_q.iter().map(|(e, HiddenLink(link))| (e, link)));
+                for (link_entity, link) in links {
+                    if link.source == entity || link.target == entity {
+                        commands.entity(link_entity).despawn_recursive();
+                    }
+                }
+                commands.entity(entity).despawn_recursive();
+            }
+            NodeAction::CenterView => {
+                events.focus.send(FocusNode {
+                    entity,
+                    zoom: false,
+                    pin: false,
+                });
+            }
+            NodeAction::Custom(_) => {}
+        }
+    }
+}
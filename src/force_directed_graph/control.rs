@@ -0,0 +1,272 @@
+use bevy::{
+    app::{App, Plugin, PreUpdate, Startup, Update},
+    color::{Alpha as _, Color},
+    ecs::{
+        change_detection::DetectChanges as _,
+        component::Component,
+        event::{Event, EventReader, EventWriter},
+        query::{Changed, With},
+        schedule::{IntoSystemConfigs as _, IntoSystemSetConfigs as _},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::{BuildChildren as _, ChildBuild as _},
+    input::{keyboard::KeyCode, ButtonInput},
+    text::{TextColor, TextFont},
+    ui::{
+        widget::{Button, Text},
+        BackgroundColor, Interaction, JustifyContent, Node, PositionType, UiRect, Val,
+    },
+    utils::default,
+};
+
+use super::SimulationSet;
+
+const BUTTON_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+const BUTTON_HOVERED_COLOR: Color = Color::srgba(0.3, 0.3, 0.3, 0.8);
+
+/// Smallest and largest time scale.
+pub const TIME_SCALE_RANGE: (f32, f32) = (0.1, 10.0);
+
+/// Play/pause state and speed of the simulation. While paused, the
+/// integration and all forces (see [SimulationSet]) are skipped, except for
+/// single steps.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct SimulationControl {
+    pub paused: bool,
+    /// Factor for the time step of the simulation, within [TIME_SCALE_RANGE]
+    pub time_scale: f32,
+    /// Number of requested single steps
+    pub pending_steps: u32,
+    /// Whether the current tick is a single step
+    stepping: bool,
+}
+
+impl Default for SimulationControl {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            time_scale: 1.0,
+            pending_steps: 0,
+            stepping: false,
+        }
+    }
+}
+
+impl SimulationControl {
+    /// Whether the simulation advances in the current tick.
+    pub fn running(&self) -> bool {
+        !self.paused || self.stepping
+    }
+}
+
+/// Event to control the simulation, see [SimulationControlPlugin].
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum ControlSimulation {
+    TogglePause,
+    /// Advance a paused simulation by one tick
+    Step,
+    /// Halve the time scale
+    Slower,
+    /// Double the time scale
+    Faster,
+    SetTimeScale(f32),
+}
+
+/// Component for the buttons of the control bar.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct ControlButton(pub ControlSimulation);
+
+/// Marker component for the text showing the play/pause state and the time
+/// scale.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ControlStatusText;
+
+/// Plugin for pausing, single-stepping and speeding up or slowing down the
+/// simulation, via [ControlSimulation] events, a control bar at the bottom or
+/// the keyboard:
+///
+/// * `Space` - play/pause
+/// * `.` - single step while paused
+/// * `-`/`=` - halve/double the time scale (0.1x to 10x)
+pub struct SimulationControlPlugin;
+
+impl Plugin for SimulationControlPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SimulationControl>()
+            .add_event::<ControlSimulation>()
+            .configure_sets(
+                PreUpdate,
+                SimulationSet::Integration.run_if(simulation_running),
+            )
+            .configure_sets(
+                Update,
+                (SimulationSet::Forces, SimulationSet::Constraints).run_if(simulation_running),
+            )
+            .add_systems(Startup, setup_control_bar)
+            .add_systems(PreUpdate, begin_step.before(SimulationSet::Integration))
+            .add_systems(
+                Update,
+                (
+                    (control_keyboard_input, control_button_interaction),
+                    apply_simulation_control,
+                    update_control_status,
+                )
+                    .chain(),
+            )
+            .add_systems(Update, end_step.after(SimulationSet::Constraints));
+    }
+}
+
+/// Run condition: false while the simulation is paused, see
+/// [SimulationControl].
+pub fn simulation_running(control: Res<SimulationControl>) -> bool {
+    control.running()
+}
+
+pub fn setup_control_bar(mut commands: Commands) {
+    // Full-width row to center the bar horizontally
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(8.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            column_gap: Val::Px(4.0),
+            ..default()
+        })
+        .with_children(|parent| {
+            for (label, command) in [
+                ("Play/Pause", ControlSimulation::TogglePause),
+                ("Step", ControlSimulation::Step),
+                ("Slower", ControlSimulation::Slower),
+                ("Faster", ControlSimulation::Faster),
+            ] {
+                parent
+                    .spawn((
+                        Button,
+                        ControlButton(command),
+                        Node {
+                            padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+                            ..default()
+                        },
+                        BackgroundColor(BUTTON_COLOR),
+                    ))
+                    .with_child((
+                        Text::new(label),
+                        TextFont {
+                            font_size: 12.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+            }
+            parent
+                .spawn((
+                    Node {
+                        padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::BLACK.with_alpha(0.6)),
+                ))
+                .with_child((
+                    ControlStatusText,
+                    Text::default(),
+                    TextFont {
+                        font_size: 12.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ));
+        });
+}
+
+/// Send [ControlSimulation] events on key presses. See
+/// [SimulationControlPlugin].
+pub fn control_keyboard_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut control_events: EventWriter<ControlSimulation>,
+) {
+    for (key, command) in [
+        (KeyCode::Space, ControlSimulation::TogglePause),
+        (KeyCode::Period, ControlSimulation::Step),
+        (KeyCode::Minus, ControlSimulation::Slower),
+        (KeyCode::Equal, ControlSimulation::Faster),
+    ] {
+        if keyboard.just_pressed(key) {
+            control_events.send(command);
+        }
+    }
+}
+
+/// Highlight hovered buttons of the control bar and send their
+/// [ControlSimulation] event when pressed.
+pub fn control_button_interaction(
+    mut buttons_q: Query<
+        (&Interaction, &ControlButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    mut control_events: EventWriter<ControlSimulation>,
+) {
+    for (interaction, ControlButton(command), mut background_color) in &mut buttons_q {
+        match interaction {
+            Interaction::Pressed => {
+                control_events.send(*command);
+            }
+            Interaction::Hovered => background_color.0 = BUTTON_HOVERED_COLOR,
+            Interaction::None => background_color.0 = BUTTON_COLOR,
+        }
+    }
+}
+
+pub fn apply_simulation_control(
+    mut control_events: EventReader<ControlSimulation>,
+    mut control: ResMut<SimulationControl>,
+) {
+    for command in control_events.read() {
+        match command {
+            ControlSimulation::TogglePause => control.paused = !control.paused,
+            ControlSimulation::Step => {
+                if control.paused {
+                    control.pending_steps += 1;
+                }
+            }
+            ControlSimulation::Slower => control.time_scale /= 2.0,
+            ControlSimulation::Faster => control.time_scale *= 2.0,
+            ControlSimulation::SetTimeScale(time_scale) => control.time_scale = *time_scale,
+        }
+        control.time_scale = control
+            .time_scale
+            .clamp(TIME_SCALE_RANGE.0, TIME_SCALE_RANGE.1);
+    }
+}
+
+/// Start a requested single step before the integration.
+pub fn begin_step(mut control: ResMut<SimulationControl>) {
+    if control.pending_steps > 0 && !control.stepping {
+        control.pending_steps -= 1;
+        control.stepping = true;
+    }
+}
+
+/// End a single step after all forces and constraints.
+pub fn end_step(mut control: ResMut<SimulationControl>) {
+    if control.stepping {
+        control.stepping = false;
+    }
+}
+
+pub fn update_control_status(
+    control: Res<SimulationControl>,
+    mut texts_q: Query<&mut Text, With<ControlStatusText>>,
+) {
+    if !control.is_changed() {
+        return;
+    }
+    for mut text in &mut texts_q {
+        text.0 = format!(
+            "{} {:.1}x",
+            if control.paused { "paused" } else { "running" },
+            control.time_scale
+        );
+    }
+}
@@ -0,0 +1,482 @@
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    color::{Alpha as _, Color},
+    ecs::{
+        change_detection::DetectChanges as _,
+        component::Component,
+        entity::Entity,
+        query::{With, Without},
+        schedule::{common_conditions::resource_exists, IntoSystemConfigs, SystemConfigs},
+        system::{Commands, Local, Query, Res, ResMut, Resource, Single},
+    },
+    gizmos::{config::GizmoConfigStore, gizmos::Gizmos},
+    hierarchy::{BuildChildren as _, ChildBuild as _, DespawnRecursiveExt as _},
+    input::{keyboard::KeyCode, ButtonInput},
+    math::Vec2,
+    text::{TextColor, TextFont},
+    transform::components::Transform,
+    ui::{
+        widget::Text, AlignItems, BackgroundColor, Display, FlexDirection, Node, PositionType,
+        UiRect, Val,
+    },
+    utils::{default, HashMap},
+};
+
+use super::{
+    common::{ForceAccumulator, MouseLocked, NodePhysics},
+    node_style,
+    tween::Tween,
+    utils::ClampF32Range as _,
+    verlet::{self, ForceScales, VerletRes},
+    SimulationSet,
+};
+
+/// Keys toggling the first nine forces of the legend.
+const FORCE_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct ForceDebugSettings {
+    /// Record the displacements and draw them. Recording costs a copy of all
+    /// node positions and forces per tick.
+    pub enabled: bool,
+    /// Length of an arrow per px of displacement
+    pub scale: f32,
+    /// Labels of the forces which are not drawn
+    pub hidden: Vec<&'static str>,
+}
+
+/// Displacement of every node by every force in the last tick: the forces in
+/// the order they were first seen, then the constraints in the order they ran.
+/// Forces are identified by their label in the [ForceAccumulator], constraints
+/// by the label given to [record_constraint]. Rebuilt every tick, so forces
+/// which stopped acting disappear.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ForceDisplacements {
+    pub forces: Vec<(&'static str, HashMap<Entity, Vec2>)>,
+}
+
+impl ForceDisplacements {
+    pub fn set(&mut self, label: &'static str, displacements: HashMap<Entity, Vec2>) {
+        match self.forces.iter_mut().find(|(force, _)| *force == label) {
+            Some((_, previous)) => *previous = displacements,
+            None => self.forces.push((label, displacements)),
+        }
+    }
+
+    /// Color of the arrows of the force at `index`.
+    pub fn color(index: usize) -> Color {
+        let palette = node_style::default_palette();
+        palette[index % palette.len()]
+    }
+}
+
+/// Positions of all nodes before the next constraint, see [record_constraint].
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ConstraintSnapshot(pub HashMap<Entity, Vec2>);
+
+/// Marker component for the legend of the forces.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ForceLegend;
+
+/// Plugin for drawing the displacement of every node by every force as a
+/// colored arrow, to diagnose oscillations and tune strengths. Forces are
+/// recorded from the [ForceAccumulator] before
+/// [verlet::apply_forces](crate::force_directed_graph::verlet::apply_forces)
+/// clears it, the constraints (which move the nodes directly) by comparing the
+/// positions around each constraint, see [record_constraint]. Nothing is
+/// recorded while disabled.
+///
+/// `F4` toggles the arrows. While they are shown, `1` to `9` toggle the forces
+/// in the order of the legend.
+pub struct ForceDebugPlugin {
+    pub enabled: bool,
+    pub scale: f32,
+}
+
+impl Default for ForceDebugPlugin {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            scale: 20.0,
+        }
+    }
+}
+
+impl Plugin for ForceDebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ForceDebugSettings {
+            enabled: self.enabled,
+            scale: self.scale,
+            hidden: Vec::new(),
+        })
+        .init_resource::<ForceDisplacements>()
+        .init_resource::<ConstraintSnapshot>()
+        .add_systems(Startup, setup_force_legend)
+        .add_systems(
+            Update,
+            (
+                record_force_displacements
+                    .after(SimulationSet::Forces)
+                    .before(verlet::apply_forces),
+                snapshot_constraints
+                    .after(verlet::apply_forces)
+                    .before(SimulationSet::Constraints),
+            )
+                .run_if(force_debug_enabled),
+        )
+        .add_systems(
+            Update,
+            (
+                toggle_force_debug,
+                update_force_legend,
+                draw_force_displacements.run_if(resource_exists::<GizmoConfigStore>),
+            )
+                .chain()
+                .after(SimulationSet::Constraints),
+        );
+    }
+}
+
+/// Run condition: true while [ForceDebugSettings::enabled]. False without the
+/// [ForceDebugPlugin].
+pub fn force_debug_enabled(settings: Option<Res<ForceDebugSettings>>) -> bool {
+    settings.is_some_and(|settings| settings.enabled)
+}
+
+/// Constraint system in [SimulationSet::Constraints], followed by recording
+/// its displacements under `label` while [force_debug_enabled]. Constraints
+/// added this way need to be chained, so each one is compared to the positions
+/// left by the previous one.
+pub fn record_constraint<M>(
+    label: &'static str,
+    constraint: impl IntoSystemConfigs<M>,
+) -> SystemConfigs {
+    (
+        constraint,
+        record_constraint_displacements(label).run_if(force_debug_enabled),
+    )
+        .chain()
+}
+
+/// Record the displacement by every force in the [ForceAccumulator]s, as
+/// [verlet::apply_forces] will apply it. Mouse locked and animated nodes are
+/// not moved by the forces, so nothing is recorded for them. This is the first
+/// recording in a tick, so it replaces all displacements of the last one.
+pub fn record_force_displacements(
+    nodes_q: Query<
+        (Entity, &ForceAccumulator),
+        (With<NodePhysics>, Without<MouseLocked>, Without<Tween>),
+    >,
+    verlet: Res<VerletRes>,
+    scales: Res<ForceScales>,
+    mut displacements: ResMut<ForceDisplacements>,
+) {
+    let mut forces = Vec::<(&'static str, HashMap<Entity, Vec2>)>::new();
+    for (entity, accumulator) in &nodes_q {
+        for (label, force) in &accumulator.forces {
+            let index = match forces.iter().position(|(other, _)| other == label) {
+                Some(index) => index,
+                None => {
+                    forces.push((label, HashMap::default()));
+                    forces.len() - 1
+                }
+            };
+            let displacement =
+                (*force * scales.get(label) * verlet.delta_secs_squared()).clamp_f32_range();
+            forces[index].1.insert(entity, displacement);
+        }
+    }
+    displacements.forces = forces;
+}
+
+/// Save the positions of all nodes before the constraints move them.
+pub fn snapshot_constraints(
+    nodes_q: Query<(Entity, &Transform), With<NodePhysics>>,
+    mut snapshot: ResMut<ConstraintSnapshot>,
+) {
+    snapshot.0 = nodes_q
+        .iter()
+        .map(|(entity, transform)| (entity, transform.translation.truncate()))
+        .collect();
+}
+
+/// Record the displacement by a constraint, compared to the
+/// [ConstraintSnapshot], and update the snapshot for the next constraint.
+pub fn record_constraint_displacements(
+    label: &'static str,
+) -> impl Fn(
+    Query<'_, '_, (Entity, &Transform), With<NodePhysics>>,
+    ResMut<'_, ConstraintSnapshot>,
+    ResMut<'_, ForceDisplacements>,
+) {
+    move |nodes_q: Query<(Entity, &Transform), With<NodePhysics>>,
+          mut snapshot: ResMut<ConstraintSnapshot>,
+          mut displacements: ResMut<ForceDisplacements>| {
+        let mut constraint_displacements = HashMap::default();
+        for (entity, transform) in &nodes_q {
+            let position = transform.translation.truncate();
+            if let Some(previous) = snapshot.0.insert(entity, position) {
+                constraint_displacements.insert(entity, position - previous);
+            }
+        }
+        displacements.set(label, constraint_displacements);
+    }
+}
+
+pub fn setup_force_legend(mut commands: Commands) {
+    commands.spawn((
+        ForceLegend,
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(40.0),
+            left: Val::Px(8.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            flex_direction: FlexDirection::Column,
+            display: Display::None,
+            ..default()
+        },
+        BackgroundColor(Color::BLACK.with_alpha(0.6)),
+    ));
+}
+
+/// Toggle the arrows, or single forces, see [ForceDebugPlugin].
+pub fn toggle_force_debug(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<ForceDebugSettings>,
+    mut displacements: ResMut<ForceDisplacements>,
+) {
+    if keyboard.just_pressed(KeyCode::F4) {
+        settings.enabled = !settings.enabled;
+        // Don't show stale displacements when enabled again
+        displacements.forces.clear();
+    }
+    if !settings.enabled {
+        return;
+    }
+    for (index, key) in FORCE_KEYS.iter().enumerate() {
+        let Some((label, _)) = displacements.forces.get(index) else {
+            break;
+        };
+        if keyboard.just_pressed(*key) {
+            let label = *label;
+            match settings.hidden.iter().position(|hidden| *hidden == label) {
+                Some(position) => {
+                    settings.hidden.remove(position);
+                }
+                None => settings.hidden.push(label),
+            }
+        }
+    }
+}
+
+/// Rebuild the legend when the settings or the recorded forces change.
+pub fn update_force_legend(
+    legend: Single<(Entity, &mut Node), With<ForceLegend>>,
+    settings: Res<ForceDebugSettings>,
+    displacements: Res<ForceDisplacements>,
+    mut labels: Local<Vec<&'static str>>,
+    mut commands: Commands,
+) {
+    if !settings.is_changed()
+        && labels
+            .iter()
+            .eq(displacements.forces.iter().map(|(label, _)| label))
+    {
+        return;
+    }
+    *labels = displacements
+        .forces
+        .iter()
+        .map(|(label, _)| *label)
+        .collect();
+
+    let (legend, mut node) = legend.into_inner();
+    node.display = if settings.enabled && !labels.is_empty() {
+        Display::Flex
+    } else {
+        Display::None
+    };
+    commands
+        .entity(legend)
+        .despawn_descendants()
+        .with_children(|parent| {
+            for (index, (label, _)) in displacements.forces.iter().enumerate() {
+                let alpha = if settings.hidden.contains(label) {
+                    0.3
+                } else {
+                    1.0
+                };
+                parent
+                    .spawn(Node {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(6.0),
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        row.spawn((
+                            Node {
+                                width: Val::Px(10.0),
+                                height: Val::Px(10.0),
+                                ..default()
+                            },
+                            BackgroundColor(ForceDisplacements::color(index).with_alpha(alpha)),
+                        ));
+                        let key = if index < FORCE_KEYS.len() {
+                            format!("{} ", index + 1)
+                        } else {
+                            String::new()
+                        };
+                        row.spawn((
+                            Text::new(format!("{}{}", key, label)),
+                            TextFont {
+                                font_size: 12.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE.with_alpha(alpha)),
+                        ));
+                    });
+            }
+        });
+}
+
+/// Draw an arrow per node and force, starting at the node.
+pub fn draw_force_displacements(
+    mut gizmos: Gizmos,
+    nodes_q: Query<&Transform, With<NodePhysics>>,
+    settings: Res<ForceDebugSettings>,
+    displacements: Res<ForceDisplacements>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    for (index, (label, force_displacements)) in displacements.forces.iter().enumerate() {
+        if settings.hidden.contains(label) {
+            continue;
+        }
+        let color = ForceDisplacements::color(index);
+        for (entity, displacement) in force_displacements {
+            let Ok(transform) = nodes_q.get(*entity) else {
+                continue;
+            };
+            if *displacement == Vec2::ZERO {
+                continue;
+            }
+            let start = transform.translation.truncate();
+            gizmos.arrow_2d(start, start + *displacement * settings.scale, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{record_constraint, ForceDebugPlugin, ForceDisplacements};
+    use crate::force_directed_graph::{
+        common::NodePhysics,
+        forces::custom::{AddForce as _, Force, ForceContext, ForceNode},
+        verlet::{apply_forces, ForceScales, VerletRes},
+        SimulationSet,
+    };
+    use bevy::{
+        app::{App, Update},
+        ecs::{
+            schedule::{IntoSystemConfigs as _, IntoSystemSetConfigs as _},
+            system::Query,
+        },
+        input::{keyboard::KeyCode, ButtonInput},
+        math::Vec2,
+        time::Time,
+        transform::components::Transform,
+        utils::HashMap,
+    };
+
+    struct Push;
+
+    impl Force for Push {
+        fn apply(&self, _node: &ForceNode, _ctx: &ForceContext) -> Vec2 {
+            Vec2::new(4.0, 0.0)
+        }
+    }
+
+    #[test]
+    fn test_force_displacements() {
+        let mut app = App::new();
+        let mut verlet = VerletRes::default();
+        verlet.update(0.5);
+        app.insert_resource(verlet)
+            .init_resource::<ForceScales>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<Time>()
+            .add_plugins(ForceDebugPlugin {
+                enabled: true,
+                scale: 1.0,
+            })
+            .configure_sets(
+                Update,
+                (SimulationSet::Forces, SimulationSet::Constraints).chain(),
+            )
+            // Custom forces are recorded under their label like any other
+            .add_force("push", Push)
+            .add_systems(
+                Update,
+                (
+                    apply_forces
+                        .after(SimulationSet::Forces)
+                        .before(SimulationSet::Constraints),
+                    (
+                        record_constraint("up", |mut nodes_q: Query<&mut Transform>| {
+                            for mut transform in &mut nodes_q {
+                                transform.translation.y += 2.0;
+                            }
+                        }),
+                        record_constraint("left", |mut nodes_q: Query<&mut Transform>| {
+                            for mut transform in &mut nodes_q {
+                                transform.translation.x -= 3.0;
+                            }
+                        }),
+                    )
+                        .chain()
+                        .in_set(SimulationSet::Constraints),
+                ),
+            );
+        let node = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                NodePhysics::from_transform(Transform::default()),
+            ))
+            .id();
+        // Left over from a force which doesn't act anymore
+        app.world_mut()
+            .resource_mut::<ForceDisplacements>()
+            .set("stale", HashMap::from_iter([(node, Vec2::ONE)]));
+        app.update();
+
+        let displacements = app.world().resource::<ForceDisplacements>();
+        assert_eq!(displacements.forces.len(), 3);
+        // Time step of 0.5 s: 4.0 * 0.5² px
+        assert_eq!(displacements.forces[0].0, "push");
+        assert_eq!(displacements.forces[0].1[&node], Vec2::new(1.0, 0.0));
+        assert_eq!(displacements.forces[1].0, "up");
+        assert_eq!(displacements.forces[1].1[&node], Vec2::new(0.0, 2.0));
+        assert_eq!(displacements.forces[2].0, "left");
+        assert_eq!(displacements.forces[2].1[&node], Vec2::new(-3.0, 0.0));
+        assert_eq!(
+            app.world()
+                .get::<Transform>(node)
+                .unwrap()
+                .translation
+                .truncate(),
+            Vec2::new(-2.0, 2.0)
+        );
+    }
+}
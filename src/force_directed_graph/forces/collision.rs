@@ -0,0 +1,75 @@
+use crate::force_directed_graph::{
+    common::{MouseLocked, NodePhysics, NodeRadius},
+    utils::{ClampF32Range, FiniteOrRandom as _},
+};
+use bevy::{
+    ecs::{query::With, system::Query},
+    transform::components::Transform,
+};
+
+/// Label of the constraint in the
+/// [ForceDisplacements](crate::force_directed_graph::force_debug::ForceDisplacements).
+pub const LABEL: &str = "collision";
+
+/// This is not really a force. It pushes overlapping nodes apart until their
+/// circles (see [NodeRadius]) do not overlap anymore. Nodes which are
+/// drag-and-dropped are not moved, the other node takes the whole correction.
+///
+/// * `padding` - minimum gap between two circles in px
+pub fn apply_collision(
+    padding: f32,
+) -> impl Fn(
+    Query<
+        '_,
+        '_,
+        (
+            &mut Transform,
+            Option<&NodeRadius>,
+            std::option::Option<&MouseLocked>,
+        ),
+        With<NodePhysics>,
+    >,
+) {
+    move |mut transforms_q: Query<
+        (&mut Transform, Option<&NodeRadius>, Option<&MouseLocked>),
+        With<NodePhysics>,
+    >| {
+        let mut combinations = transforms_q.iter_combinations_mut::<2>();
+        while let Some(
+            [(mut a_transform, a_radius, a_mouse_locked), (mut b_transform, b_radius, b_mouse_locked)],
+        ) = combinations.fetch_next()
+        {
+            let min_distance = a_radius.copied().unwrap_or_default().0
+                + b_radius.copied().unwrap_or_default().0
+                + padding;
+            let direction = (b_transform.translation.truncate()
+                - a_transform.translation.truncate())
+            .clamp_f32_range();
+            let distance = direction.length().clamp_f32_range();
+            if distance >= min_distance {
+                continue;
+            }
+
+            // if the direction vector is zero, normalizing will lead to NaN (-> take a random direction)
+            let direction = direction.normalize().finite_or_random_normalized();
+            let overlap = (direction * (min_distance - distance)).clamp_f32_range();
+
+            // Split the correction between both nodes, unless one of them is locked
+            let (a_share, b_share) = match (a_mouse_locked.is_some(), b_mouse_locked.is_some()) {
+                (false, false) => (0.5, 0.5),
+                (true, false) => (0.0, 1.0),
+                (false, true) => (1.0, 0.0),
+                (true, true) => (0.0, 0.0),
+            };
+            a_transform.translation =
+                (a_transform.translation - (overlap * a_share).extend(0.0)).clamp_f32_range();
+            b_transform.translation =
+                (b_transform.translation + (overlap * b_share).extend(0.0)).clamp_f32_range();
+
+            #[cfg(debug_assertions)]
+            assert!(a_transform.is_finite(), "Not finite: {:?}", a_transform);
+            #[cfg(debug_assertions)]
+            assert!(b_transform.is_finite(), "Not finite: {:?}", b_transform);
+        }
+    }
+}
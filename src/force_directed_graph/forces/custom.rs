@@ -0,0 +1,253 @@
+use crate::force_directed_graph::{
+    common::{ForceAccumulator, MouseLocked, NodePhysics, NodeRadius},
+    utils::{ClampF32Range as _, FiniteOr as _},
+    verlet::VerletRes,
+    SimulationSet,
+};
+use bevy::{
+    app::{App, Update},
+    ecs::{
+        entity::Entity,
+        schedule::IntoSystemConfigs as _,
+        system::{Query, Res},
+    },
+    math::Vec2,
+    time::Time,
+    transform::components::Transform,
+};
+
+/// State of a node as seen by a [Force] or [PairwiseForce].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForceNode {
+    pub entity: Entity,
+    pub position: Vec2,
+    /// Velocity in px/s, approximated from the previous position
+    pub velocity: Vec2,
+    /// Radius in px, see [NodeRadius]
+    pub radius: f32,
+}
+
+/// State of the simulation passed to every [Force] and [PairwiseForce].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForceContext {
+    /// Time step of the simulation in s, see [VerletRes::delta_secs]
+    pub delta_secs: f32,
+    pub elapsed_secs: f32,
+    pub node_count: usize,
+}
+
+/// A force acting on every node on its own, e.g. gravity. Register it with
+/// [AddForce::add_force].
+pub trait Force: Send + Sync + 'static {
+    /// Force acting on `node` in kg*px/s^2. It must not be multiplied by the
+    /// time step.
+    fn apply(&self, node: &ForceNode, ctx: &ForceContext) -> Vec2;
+}
+
+/// A force acting between every pair of nodes, e.g. repulsion. Register it with
+/// [AddForce::add_pairwise_force].
+pub trait PairwiseForce: Send + Sync + 'static {
+    /// Force acting on `b` in kg*px/s^2. The opposite force acts on `a`. It must
+    /// not be multiplied by the time step.
+    fn apply(&self, a: &ForceNode, b: &ForceNode, ctx: &ForceContext) -> Vec2;
+}
+
+/// Registration of [Force]s and [PairwiseForce]s. Every force is added as a
+/// system in [SimulationSet::Forces] which adds into the [ForceAccumulator]
+/// under its label, so the force debugging shows it like any built-in force.
+/// Results are clamped, non-finite results are dropped, mouse locked nodes are
+/// not affected, and the time step as well as
+/// [crate::force_directed_graph::verlet::ForceScales] are applied by
+/// [crate::force_directed_graph::verlet::apply_forces].
+pub trait AddForce {
+    fn add_force(&mut self, label: &'static str, force: impl Force) -> &mut Self;
+    fn add_pairwise_force(&mut self, label: &'static str, force: impl PairwiseForce) -> &mut Self;
+}
+
+impl AddForce for App {
+    fn add_force(&mut self, label: &'static str, force: impl Force) -> &mut Self {
+        self.add_systems(
+            Update,
+            apply_force(label, force).in_set(SimulationSet::Forces),
+        )
+    }
+
+    fn add_pairwise_force(&mut self, label: &'static str, force: impl PairwiseForce) -> &mut Self {
+        self.add_systems(
+            Update,
+            apply_pairwise_force(label, force).in_set(SimulationSet::Forces),
+        )
+    }
+}
+
+type ForceNodesQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Transform,
+        &'static NodePhysics,
+        Option<&'static NodeRadius>,
+        &'static mut ForceAccumulator,
+        Option<&'static MouseLocked>,
+    ),
+>;
+
+fn force_context(nodes_q: &ForceNodesQuery, verlet: &VerletRes, time: &Time) -> ForceContext {
+    ForceContext {
+        delta_secs: verlet.delta_secs(),
+        elapsed_secs: time.elapsed_secs(),
+        node_count: nodes_q.iter().count(),
+    }
+}
+
+/// Nodes which can be moved, and those which are mouse locked.
+fn force_nodes(nodes_q: &ForceNodesQuery, ctx: &ForceContext) -> Vec<(ForceNode, bool)> {
+    nodes_q
+        .iter()
+        .map(
+            |(entity, transform, node_physics, radius, _, mouse_locked)| {
+                let position = transform.translation.truncate();
+                let velocity = ((position - node_physics.previous_position).clamp_f32_range()
+                    / ctx.delta_secs)
+                    .clamp_f32_range()
+                    .finite_or(Vec2::ZERO);
+                let node = ForceNode {
+                    entity,
+                    position,
+                    velocity,
+                    radius: radius.copied().unwrap_or_default().0,
+                };
+                (node, mouse_locked.is_some())
+            },
+        )
+        .collect()
+}
+
+/// Clamp the force and drop it if it is not finite.
+fn sanitize(force: Vec2) -> Vec2 {
+    force.clamp_f32_range().finite_or(Vec2::ZERO)
+}
+
+/// System for a [Force], see [AddForce::add_force].
+pub fn apply_force(
+    label: &'static str,
+    force: impl Force,
+) -> impl Fn(ForceNodesQuery, Res<'_, VerletRes>, Res<'_, Time>) {
+    move |mut nodes_q: ForceNodesQuery, verlet: Res<VerletRes>, time: Res<Time>| {
+        let ctx = force_context(&nodes_q, &verlet, &time);
+        for (node, mouse_locked) in force_nodes(&nodes_q, &ctx) {
+            if mouse_locked {
+                continue;
+            }
+            let node_force = sanitize(force.apply(&node, &ctx));
+            if let Ok((.., mut accumulator, _)) = nodes_q.get_mut(node.entity) {
+                accumulator.add(label, node_force);
+            }
+        }
+    }
+}
+
+/// System for a [PairwiseForce], see [AddForce::add_pairwise_force].
+pub fn apply_pairwise_force(
+    label: &'static str,
+    force: impl PairwiseForce,
+) -> impl Fn(ForceNodesQuery, Res<'_, VerletRes>, Res<'_, Time>) {
+    move |mut nodes_q: ForceNodesQuery, verlet: Res<VerletRes>, time: Res<Time>| {
+        let ctx = force_context(&nodes_q, &verlet, &time);
+        let nodes = force_nodes(&nodes_q, &ctx);
+        let mut forces = vec![Vec2::ZERO; nodes.len()];
+        for a in 0..nodes.len() {
+            for b in (a + 1)..nodes.len() {
+                let pair_force = sanitize(force.apply(&nodes[a].0, &nodes[b].0, &ctx));
+                forces[a] = (forces[a] - pair_force).clamp_f32_range();
+                forces[b] = (forces[b] + pair_force).clamp_f32_range();
+            }
+        }
+        for ((node, mouse_locked), node_force) in nodes.into_iter().zip(forces) {
+            if mouse_locked {
+                continue;
+            }
+            if let Ok((.., mut accumulator, _)) = nodes_q.get_mut(node.entity) {
+                accumulator.add(label, node_force);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AddForce as _, Force, ForceContext, ForceNode, PairwiseForce};
+    use crate::force_directed_graph::{
+        common::{ForceAccumulator, MouseLocked, NodePhysics},
+        verlet::VerletRes,
+    };
+    use bevy::{
+        app::App,
+        math::{Vec2, Vec3},
+        time::Time,
+        transform::components::Transform,
+    };
+
+    struct Wind;
+
+    impl Force for Wind {
+        fn apply(&self, node: &ForceNode, _ctx: &ForceContext) -> Vec2 {
+            // Not finite for nodes left of the origin
+            if node.position.x > 0.0 {
+                Vec2::X
+            } else {
+                Vec2::NAN
+            }
+        }
+    }
+
+    struct Spring;
+
+    impl PairwiseForce for Spring {
+        fn apply(&self, a: &ForceNode, b: &ForceNode, _ctx: &ForceContext) -> Vec2 {
+            a.position - b.position
+        }
+    }
+
+    #[test]
+    fn test_add_force() {
+        let mut app = App::new();
+        let mut verlet = VerletRes::default();
+        verlet.update(0.5);
+        app.insert_resource(verlet)
+            .init_resource::<Time>()
+            .add_force("wind", Wind)
+            .add_pairwise_force("spring", Spring);
+
+        let spawn = |app: &mut App, x: f32| {
+            let transform = Transform::from_translation(Vec3::new(x, 0.0, 0.0));
+            app.world_mut()
+                .spawn((transform, NodePhysics::from_transform(transform)))
+                .id()
+        };
+        let a = spawn(&mut app, 1.0);
+        let b = spawn(&mut app, -2.0);
+        let locked = spawn(&mut app, 4.0);
+        app.world_mut().entity_mut(locked).insert(MouseLocked {
+            velocity: Vec2::ZERO,
+        });
+
+        app.update();
+
+        let force = |entity, label| {
+            let accumulator = app.world().get::<ForceAccumulator>(entity).unwrap();
+            accumulator
+                .forces
+                .iter()
+                .find(|(other, _)| *other == label)
+                .map(|(_, force)| *force)
+        };
+        assert_eq!(force(a, "wind"), Some(Vec2::X));
+        assert_eq!(force(a, "spring"), Some(Vec2::ZERO));
+        assert_eq!(force(b, "wind"), Some(Vec2::ZERO));
+        assert_eq!(force(b, "spring"), Some(Vec2::new(9.0, 0.0)));
+        assert_eq!(force(locked, "wind"), None);
+        assert_eq!(force(locked, "spring"), None);
+    }
+}
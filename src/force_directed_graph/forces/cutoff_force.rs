@@ -1,7 +1,6 @@
 use crate::force_directed_graph::{
-    common::{MouseLocked, NodePhysics},
+    common::{ForceAccumulator, MouseLocked, NodePhysics},
     utils::ClampF32Range as _,
-    verlet::VerletRes,
 };
 use bevy::{
     ecs::{
@@ -10,35 +9,36 @@ use bevy::{
     },
     math::Vec2,
     time::Time,
-    transform::components::Transform,
 };
 
+/// Label of the force in the [ForceAccumulator].
+pub const LABEL: &str = "cutoff";
+
 /// Add a gravity force.
 pub fn apply_cutoff_force(
     strength: f32,
 ) -> impl Fn(
-    Query<'_, '_, (&mut Transform, Option<&MouseLocked>), With<NodePhysics>>,
-    Res<'_, VerletRes>,
+    Query<'_, '_, (&mut ForceAccumulator, Option<&MouseLocked>), With<NodePhysics>>,
     Res<'_, Time>,
 ) {
-    move |mut transforms_q: Query<(&mut Transform, Option<&MouseLocked>), With<NodePhysics>>,
-          verlet: Res<VerletRes>,
+    move |mut accumulators_q: Query<
+        (&mut ForceAccumulator, Option<&MouseLocked>),
+        With<NodePhysics>,
+    >,
           time: Res<Time>| {
         if time.delta_secs() > 1.0 {
             return;
         }
 
-        transforms_q
+        accumulators_q
             .iter_mut()
             .filter(|(_, mouse_locked)| mouse_locked.is_none())
-            .for_each(|(mut transform, _)| {
-                let force =
-                    (strength * Vec2::NEG_Y * verlet.delta_secs_squared()).clamp_f32_range();
+            .for_each(|(mut accumulator, _)| {
+                let force = (strength * Vec2::NEG_Y).clamp_f32_range();
 
-                transform.translation =
-                    (transform.translation + force.extend(0.0)).clamp_f32_range();
+                accumulator.add(LABEL, force);
                 #[cfg(debug_assertions)]
-                assert!(transform.is_finite(), "Not finite: {:?}", transform);
+                assert!(force.is_finite(), "Not finite: {:?}", force);
             });
     }
 }
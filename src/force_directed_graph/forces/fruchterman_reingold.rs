@@ -0,0 +1,293 @@
+use crate::force_directed_graph::{
+    common::{ForceAccumulator, GraphChanges, MouseLocked, NodeLink, NodePhysics},
+    tween::Tween,
+    utils::{ClampF32Range as _, FiniteOr as _, FiniteOrRandom as _},
+    verlet::VerletRes,
+    ForceModel,
+};
+use bevy::{
+    ecs::{
+        change_detection::DetectChanges as _,
+        entity::Entity,
+        query::Without,
+        system::{Query, Res, ResMut, Resource},
+    },
+    math::Vec2,
+    render::camera::OrthographicProjection,
+    transform::components::Transform,
+    utils::HashMap,
+};
+
+/// Label of the force in the [ForceAccumulator].
+pub const LABEL: &str = "fruchterman-reingold";
+
+/// Label of [limit_fruchterman_reingold_displacement] in the
+/// [ForceDisplacements](crate::force_directed_graph::force_debug::ForceDisplacements).
+pub const LIMIT_LABEL: &str = "fruchterman-reingold limit";
+
+/// State of the Fruchterman–Reingold simulation.
+#[derive(Resource, Debug, Default)]
+pub struct FruchtermanReingoldRes {
+    /// Maximal displacement of a node per step in px. `None` until the first
+    /// step, which initializes it to a tenth of the window width.
+    pub temperature: Option<f32>,
+}
+
+impl FruchtermanReingoldRes {
+    /// Restart the cooling schedule, e.g. after the graph was changed.
+    pub fn reheat(&mut self) {
+        self.temperature = None;
+    }
+}
+
+/// Compute the Fruchterman–Reingold displacement of every node.
+///
+/// Nodes attract each other along links with `d^2 / k` and all nodes repell
+/// each other with `k^2 / d`, so two linked nodes are in equilibrium at a
+/// distance of `k`. The displacement is not yet limited by the temperature.
+pub fn fruchterman_reingold_displacements(
+    positions: &[Vec2],
+    links: &[(usize, usize)],
+    k: f32,
+) -> Vec<Vec2> {
+    let mut displacements = vec![Vec2::ZERO; positions.len()];
+
+    // Repulsion between all pairs of nodes
+    for a in 0..positions.len() {
+        for b in (a + 1)..positions.len() {
+            let direction = (positions[a] - positions[b]).clamp_f32_range();
+            // this also prevents overly big forces if nodes get too close
+            let distance = direction.length().clamp(1.0, f32::MAX);
+            // if the direction vector is zero, normalizing will lead to NaN (-> take a random direction)
+            let direction = direction.normalize().finite_or_random_normalized();
+
+            let force = (direction * (k * k / distance).clamp_f32_range()).clamp_f32_range();
+            displacements[a] = (displacements[a] + force).clamp_f32_range();
+            displacements[b] = (displacements[b] - force).clamp_f32_range();
+        }
+    }
+
+    // Attraction along links
+    for &(source, target) in links {
+        let direction = (positions[target] - positions[source]).clamp_f32_range();
+        let distance = direction.length().clamp_f32_range();
+        let direction = direction.normalize().finite_or(Vec2::ZERO);
+
+        let force = (direction * (distance * distance / k).clamp_f32_range()).clamp_f32_range();
+        displacements[source] = (displacements[source] + force).clamp_f32_range();
+        displacements[target] = (displacements[target] - force).clamp_f32_range();
+    }
+
+    displacements
+}
+
+/// Alternative to the verlet-based force set: the classic Fruchterman–Reingold
+/// model with `k = c * sqrt(area / n)`, where `area` is the visible window area.
+///
+/// The displacement of every node per step is limited by a temperature which
+/// cools down over time (see [FruchtermanReingoldRes]). Unlike the other forces,
+/// this one does not conserve momentum: the force also cancels the inertia
+/// added by the verlet integration, so every node moves by exactly its
+/// displacement per step. Other forces still add into the accumulator, so the
+/// summed displacement is limited again by
+/// [limit_fruchterman_reingold_displacement], which also cools down.
+///
+/// * `c` - scales the ideal link length `k` (1.0 in the original paper)
+pub fn apply_fruchterman_reingold(
+    c: f32,
+) -> impl Fn(
+    Query<
+        '_,
+        '_,
+        (
+            Entity,
+            &Transform,
+            &NodePhysics,
+            &mut ForceAccumulator,
+            Option<&MouseLocked>,
+        ),
+    >,
+    Query<'_, '_, &NodeLink, Without<NodePhysics>>,
+    Query<'_, '_, &OrthographicProjection>,
+    Res<'_, VerletRes>,
+    ResMut<'_, FruchtermanReingoldRes>,
+) {
+    move |mut nodes_q: Query<(
+        Entity,
+        &Transform,
+        &NodePhysics,
+        &mut ForceAccumulator,
+        Option<&MouseLocked>,
+    )>,
+          links_q: Query<&NodeLink, Without<NodePhysics>>,
+          projection_q: Query<&OrthographicProjection>,
+          verlet: Res<VerletRes>,
+          mut fruchterman_reingold: ResMut<FruchtermanReingoldRes>| {
+        let node_count = nodes_q.iter().count();
+        if node_count == 0 {
+            return;
+        }
+
+        let area = projection_q
+            .get_single()
+            .map(|projection| projection.area.size())
+            .unwrap_or(Vec2::splat(1000.0));
+        let k = (c * ((area.x * area.y).clamp_f32_range() / node_count as f32).sqrt())
+            .clamp_f32_range();
+        let temperature = *fruchterman_reingold
+            .temperature
+            .get_or_insert(area.x / 10.0);
+
+        let indices = nodes_q
+            .iter()
+            .enumerate()
+            .map(|(i, (entity, ..))| (entity, i))
+            .collect::<HashMap<_, _>>();
+        let positions = nodes_q
+            .iter()
+            .map(|(_, transform, ..)| transform.translation.truncate())
+            .collect::<Vec<_>>();
+        let links = links_q
+            .iter()
+            .filter_map(|link| Some((*indices.get(&link.source)?, *indices.get(&link.target)?)))
+            .collect::<Vec<_>>();
+
+        let displacements = fruchterman_reingold_displacements(&positions, &links, k);
+
+        nodes_q
+            .iter_mut()
+            .zip(displacements)
+            .filter(|((.., mouse_locked), _)| mouse_locked.is_none())
+            .for_each(
+                |((_, transform, node_physics, mut accumulator, _), displacement)| {
+                    // Limit the displacement to the temperature
+                    let displacement = displacement.clamp_length_max(temperature);
+                    // The verlet integration already moved the node by its
+                    // inertia in this tick
+                    let inertia = (transform.translation.truncate()
+                        - node_physics.previous_position)
+                        .clamp_f32_range();
+
+                    let force = ((displacement - inertia).clamp_f32_range()
+                        / verlet.delta_secs_squared())
+                    .clamp_f32_range()
+                    .finite_or(Vec2::ZERO);
+                    accumulator.add(LABEL, force);
+                },
+            );
+    }
+}
+
+/// Limit the displacement of every node in this step, i.e. the sum of all
+/// forces, to the temperature and cool down afterwards. Runs as a constraint,
+/// after [crate::force_directed_graph::verlet::apply_forces].
+///
+/// * `cooling` - factor applied to the temperature every step (e.g. 0.99)
+/// * `min_temperature` - the temperature does not cool down below this value
+///   (in px), so the graph keeps reacting to user input
+pub fn limit_fruchterman_reingold_displacement(
+    cooling: f32,
+    min_temperature: f32,
+) -> impl Fn(
+    Query<'_, '_, (&mut Transform, &NodePhysics), (Without<MouseLocked>, Without<Tween>)>,
+    ResMut<'_, FruchtermanReingoldRes>,
+) {
+    move |mut nodes_q: Query<
+        (&mut Transform, &NodePhysics),
+        (Without<MouseLocked>, Without<Tween>),
+    >,
+          mut fruchterman_reingold: ResMut<FruchtermanReingoldRes>| {
+        let Some(temperature) = fruchterman_reingold.temperature else {
+            return;
+        };
+
+        for (mut transform, node_physics) in &mut nodes_q {
+            // The previous position is the position before this step
+            let displacement = (transform.translation.truncate() - node_physics.previous_position)
+                .clamp_f32_range();
+            if displacement.length() > temperature {
+                let position =
+                    node_physics.previous_position + displacement.clamp_length_max(temperature);
+                transform.translation = position.extend(transform.translation.z);
+            }
+        }
+
+        fruchterman_reingold.temperature =
+            Some((temperature * cooling).clamp(min_temperature, f32::MAX));
+    }
+}
+
+/// Restart the cooling schedule when nodes or links were added or removed, or
+/// when the [ForceModel] was switched to Fruchterman–Reingold.
+pub fn reheat_fruchterman_reingold(
+    mut graph_changes: GraphChanges,
+    force_model: Res<ForceModel>,
+    mut fruchterman_reingold: ResMut<FruchtermanReingoldRes>,
+) {
+    if graph_changes.any() || force_model.is_changed() {
+        fruchterman_reingold.reheat();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        fruchterman_reingold_displacements, limit_fruchterman_reingold_displacement,
+        FruchtermanReingoldRes,
+    };
+    use crate::force_directed_graph::common::NodePhysics;
+    use bevy::{
+        app::{App, Update},
+        math::{Vec2, Vec3},
+        transform::components::Transform,
+    };
+
+    #[test]
+    fn test_linked_nodes_in_equilibrium_at_k() {
+        let positions = [Vec2::new(0.0, 0.0), Vec2::new(50.0, 0.0)];
+
+        let displacements = fruchterman_reingold_displacements(&positions, &[(0, 1)], 50.0);
+
+        assert!(displacements[0].length() < 1e-3, "{:?}", displacements);
+        assert!(displacements[1].length() < 1e-3, "{:?}", displacements);
+    }
+
+    #[test]
+    fn test_unlinked_nodes_repell() {
+        let positions = [Vec2::new(0.0, 0.0), Vec2::new(50.0, 0.0)];
+
+        let displacements = fruchterman_reingold_displacements(&positions, &[], 50.0);
+
+        assert_eq!(displacements, [Vec2::new(-50.0, 0.0), Vec2::new(50.0, 0.0)]);
+    }
+
+    #[test]
+    fn test_limit_summed_displacement() {
+        let mut app = App::new();
+        app.insert_resource(FruchtermanReingoldRes {
+            temperature: Some(10.0),
+        })
+        .add_systems(Update, limit_fruchterman_reingold_displacement(0.5, 1.0));
+        // Moved by 30 px in this step, e.g. by another force
+        let node = app
+            .world_mut()
+            .spawn((
+                Transform::from_translation(Vec3::new(30.0, 0.0, 1.0)),
+                NodePhysics {
+                    previous_position: Vec2::ZERO,
+                },
+            ))
+            .id();
+
+        app.update();
+
+        assert_eq!(
+            app.world().get::<Transform>(node).unwrap().translation,
+            Vec3::new(10.0, 0.0, 1.0)
+        );
+        assert_eq!(
+            app.world().resource::<FruchtermanReingoldRes>().temperature,
+            Some(5.0)
+        );
+    }
+}
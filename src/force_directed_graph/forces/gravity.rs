@@ -1,35 +1,32 @@
 use crate::force_directed_graph::{
-    common::{MouseLocked, NodePhysics},
+    common::{ForceAccumulator, MouseLocked, NodePhysics},
     utils::ClampF32Range as _,
-    verlet::VerletRes,
 };
 use bevy::{
-    ecs::{
-        query::With,
-        system::{Query, Res},
-    },
+    ecs::{query::With, system::Query},
     math::Vec2,
-    transform::components::Transform,
 };
 
+/// Label of the force in the [ForceAccumulator].
+pub const LABEL: &str = "gravity";
+
 /// Add a gravity force.
 pub fn apply_gravity_force(
     strength: f32,
-) -> impl Fn(Query<'_, '_, (&mut Transform, Option<&MouseLocked>), With<NodePhysics>>, Res<'_, VerletRes>)
-{
-    move |mut transforms_q: Query<(&mut Transform, Option<&MouseLocked>), With<NodePhysics>>,
-          verlet: Res<VerletRes>| {
-        transforms_q
+) -> impl Fn(Query<'_, '_, (&mut ForceAccumulator, Option<&MouseLocked>), With<NodePhysics>>) {
+    move |mut accumulators_q: Query<
+        (&mut ForceAccumulator, Option<&MouseLocked>),
+        With<NodePhysics>,
+    >| {
+        accumulators_q
             .iter_mut()
             .filter(|(_, mouse_locked)| mouse_locked.is_none())
-            .for_each(|(mut transform, _)| {
-                let force =
-                    (strength * Vec2::NEG_Y * verlet.delta_secs_squared()).clamp_f32_range();
+            .for_each(|(mut accumulator, _)| {
+                let force = (strength * Vec2::NEG_Y).clamp_f32_range();
 
-                transform.translation =
-                    (transform.translation + force.extend(0.0)).clamp_f32_range();
+                accumulator.add(LABEL, force);
                 #[cfg(debug_assertions)]
-                assert!(transform.is_finite(), "Not finite: {:?}", transform);
+                assert!(force.is_finite(), "Not finite: {:?}", force);
             });
     }
 }
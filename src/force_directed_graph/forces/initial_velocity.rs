@@ -1,5 +1,5 @@
 use crate::force_directed_graph::{
-    common::{MouseLocked, NodePhysics},
+    common::{ForceAccumulator, MouseLocked, NodePhysics},
     utils::ClampF32Range as _,
     verlet::VerletRes,
 };
@@ -10,32 +10,35 @@ use bevy::{
     },
     math::Vec2,
     time::Time,
-    transform::components::Transform,
 };
 
-/// Apply an initial velocity to nodes that are not mouse locked.
+/// Label of the force in the [ForceAccumulator].
+pub const LABEL: &str = "initial velocity";
+
+/// Apply an initial velocity to nodes that are not mouse locked. In the first
+/// tick, the force nudges every node by `velocity` times the time step.
 /// * `velocity` - velocity in px/s (mass is irrelevant)
 pub fn apply_initial_velocity(
     velocity: f32,
 ) -> impl Fn(
-    Query<'_, '_, (&mut Transform, Option<&MouseLocked>), With<NodePhysics>>,
+    Query<'_, '_, (&mut ForceAccumulator, Option<&MouseLocked>), With<NodePhysics>>,
     Res<'_, VerletRes>,
     Res<'_, Time>,
 ) {
-    move |mut transforms_q: Query<(&mut Transform, Option<&MouseLocked>), With<NodePhysics>>,
+    move |mut nodes_q: Query<(&mut ForceAccumulator, Option<&MouseLocked>), With<NodePhysics>>,
           verlet: Res<VerletRes>,
           time: Res<Time>| {
         if time.elapsed_secs() == 0.0 {
-            transforms_q
+            // Moves the node by `velocity * delta_secs` once the time step is
+            // applied, see [crate::force_directed_graph::verlet::apply_forces]
+            let force = (velocity * Vec2::NEG_Y / verlet.delta_secs()).clamp_f32_range();
+            nodes_q
                 .iter_mut()
                 .filter(|(_, mouse_locked)| mouse_locked.is_none())
-                .for_each(|(mut transform, _)| {
-                    let nudge = (velocity * Vec2::NEG_Y * verlet.delta_secs()).clamp_f32_range();
-
-                    transform.translation =
-                        (transform.translation + nudge.extend(0.0)).clamp_f32_range();
+                .for_each(|(mut accumulator, _)| {
+                    accumulator.add(LABEL, force);
                     #[cfg(debug_assertions)]
-                    assert!(transform.is_finite(), "Not finite: {:?}", transform);
+                    assert!(force.is_finite(), "Not finite: {:?}", force);
                 });
         }
     }
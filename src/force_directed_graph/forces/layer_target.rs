@@ -0,0 +1,60 @@
+use crate::force_directed_graph::{
+    common::{ForceAccumulator, MouseLocked, NodePhysics},
+    utils::ClampF32Range as _,
+};
+use bevy::{
+    ecs::{component::Component, query::With, system::Query},
+    math::Vec2,
+    transform::components::Transform,
+};
+
+/// Label of the force in the [ForceAccumulator].
+pub const LABEL: &str = "layer target";
+
+/// Component for nodes which are pulled towards a fixed y coordinate, e.g. the
+/// layer of a hierarchical layout.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct LayerTarget {
+    pub y: f32,
+}
+
+/// Add a spring force pulling nodes towards the y coordinate of their
+/// [LayerTarget]. Nodes without a target are not affected.
+/// * `strength` - spring force in kg*px/s^2 per px of distance to the target
+pub fn apply_layer_target_force(
+    strength: f32,
+) -> impl Fn(
+    Query<
+        '_,
+        '_,
+        (
+            &Transform,
+            &LayerTarget,
+            &mut ForceAccumulator,
+            Option<&MouseLocked>,
+        ),
+        With<NodePhysics>,
+    >,
+) {
+    move |mut transforms_q: Query<
+        (
+            &Transform,
+            &LayerTarget,
+            &mut ForceAccumulator,
+            Option<&MouseLocked>,
+        ),
+        With<NodePhysics>,
+    >| {
+        transforms_q
+            .iter_mut()
+            .filter(|(_, _, _, mouse_locked)| mouse_locked.is_none())
+            .for_each(|(transform, layer_target, mut accumulator, _)| {
+                let delta_y = (layer_target.y - transform.translation.y).clamp_f32_range();
+                let force = (Vec2::Y * delta_y * strength).clamp_f32_range();
+
+                accumulator.add(LABEL, force);
+                #[cfg(debug_assertions)]
+                assert!(force.is_finite(), "Not finite: {:?}", force);
+            });
+    }
+}
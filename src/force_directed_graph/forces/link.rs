@@ -1,16 +1,18 @@
 use crate::force_directed_graph::{
-    common::{MouseLocked, NodeLink, NodePhysics},
+    common::{ForceAccumulator, LinkWeight, MouseLocked, NodeLink, NodePhysics},
     utils::ClampF32Range as _,
-    verlet::VerletRes,
 };
 use bevy::{
     ecs::{
         query::{With, Without},
-        system::{Query, Res},
+        system::Query,
     },
     transform::components::Transform,
 };
 
+/// Label of the force in the [ForceAccumulator].
+pub const LABEL: &str = "link";
+
 /// Add a spring force between two nodes. Equation: F = strength * (distance -
 ///   target_distance) / distance = strength * (1 - target_distance/direction)
 ///
@@ -27,22 +29,30 @@ use bevy::{
 /// * `strength_min` - minimum (absolute) force acting on the two nodes (e.g. 0.0)
 /// * `strength_max` - maximum (absolute) force acting on the two nodes (e.g.
 ///   f32::MAX)
+/// * `weighted` - if true, the strength is multiplied by the link's
+///   [LinkWeight] (if any), so that strong relationships pull harder
 ///
 pub fn apply_link_force(
     strength: f32,
     strength_max: f32,
+    weighted: bool,
 ) -> impl Fn(
-    Query<'_, '_, &NodeLink, Without<NodePhysics>>,
-    Query<'_, '_, (&mut Transform, std::option::Option<&MouseLocked>), With<NodePhysics>>,
-    Res<'_, VerletRes>,
+    Query<'_, '_, (&NodeLink, Option<&LinkWeight>), Without<NodePhysics>>,
+    Query<'_, '_, (&Transform, &mut ForceAccumulator, Option<&MouseLocked>), With<NodePhysics>>,
 ) {
-    move |links_q: Query<&NodeLink, Without<NodePhysics>>,
-          mut transforms_q: Query<(&mut Transform, Option<&MouseLocked>), With<NodePhysics>>,
-          verlet: Res<VerletRes>| {
-        links_q.iter().for_each(|link| {
-            let position_delta = {
-                let (source_transform, _) = transforms_q.get(link.source).unwrap();
-                let (target_transform, _) = transforms_q.get(link.target).unwrap();
+    move |links_q: Query<(&NodeLink, Option<&LinkWeight>), Without<NodePhysics>>,
+          mut nodes_q: Query<
+        (&Transform, &mut ForceAccumulator, Option<&MouseLocked>),
+        With<NodePhysics>,
+    >| {
+        links_q.iter().for_each(|(link, weight)| {
+            let strength = match weight {
+                Some(weight) if weighted => (strength * weight.0).clamp_f32_range(),
+                _ => strength,
+            };
+            let force = {
+                let (source_transform, _, _) = nodes_q.get(link.source).unwrap();
+                let (target_transform, _, _) = nodes_q.get(link.target).unwrap();
 
                 let source_position = source_transform.translation.truncate();
                 let target_position = target_transform.translation.truncate();
@@ -59,32 +69,19 @@ pub fn apply_link_force(
                 let force_sign = delta_distance.signum();
                 let force = force_sign * force_abs;
 
-                ((direction * force).clamp_f32_range() * verlet.delta_secs_squared())
-                    .clamp_f32_range()
+                (direction * force).clamp_f32_range()
             };
+            #[cfg(debug_assertions)]
+            assert!(force.is_finite(), "Not finite: {:?}", force);
 
-            let (mut source_transform, mouse_locked) = transforms_q.get_mut(link.source).unwrap();
+            let (_, mut source_accumulator, mouse_locked) = nodes_q.get_mut(link.source).unwrap();
             if mouse_locked.is_none() {
-                source_transform.translation =
-                    (source_transform.translation + position_delta.extend(0.0)).clamp_f32_range();
-                #[cfg(debug_assertions)]
-                assert!(
-                    source_transform.is_finite(),
-                    "Not finite: {:?}",
-                    source_transform
-                );
+                source_accumulator.add(LABEL, force);
             }
 
-            let (mut target_transform, mouse_locked) = transforms_q.get_mut(link.target).unwrap();
+            let (_, mut target_accumulator, mouse_locked) = nodes_q.get_mut(link.target).unwrap();
             if mouse_locked.is_none() {
-                target_transform.translation =
-                    (target_transform.translation - position_delta.extend(0.0)).clamp_f32_range();
-                #[cfg(debug_assertions)]
-                assert!(
-                    target_transform.is_finite(),
-                    "Not finite: {:?}",
-                    target_transform
-                );
+                target_accumulator.add(LABEL, -force);
             }
         });
     }
@@ -1,62 +1,86 @@
 use crate::force_directed_graph::{
-    common::{MouseLocked, NodePhysics},
-    utils::ClampF32Range as _,
+    common::{ForceAccumulator, MouseLocked, NodePhysics},
+    utils::{ClampF32Range as _, FiniteOr as _},
+    verlet::VerletRes,
 };
 use bevy::{
-    ecs::{query::With, system::Query},
+    ecs::{
+        query::With,
+        system::{Query, Res},
+    },
     math::Vec2,
     transform::components::Transform,
 };
 
+/// Label of the force in the [ForceAccumulator].
+pub const LABEL: &str = "mean to center";
+
 /// This is not really a force. It shifts all nodes so that their mean is in the
-/// middle.
+/// middle. The shift is added as the force which moves every node by it within
+/// one tick, so it acts on the same positions as all other forces.
 ///
 /// If one node is mouse locked, that node will not be shifted. This means that
 /// one iteration is not enough to shift the overall mean exactly in the center.
 /// With many nodes that is not significant. Also, later iterations will fix
 /// that.
 pub fn apply_mean_to_center(
-    mut transforms_q: Query<(&mut Transform, Option<&MouseLocked>), With<NodePhysics>>,
+    mut nodes_q: Query<
+        (&Transform, &mut ForceAccumulator, Option<&MouseLocked>),
+        With<NodePhysics>,
+    >,
+    verlet: Res<VerletRes>,
 ) {
     let center = Vec2::ZERO;
 
     // TODO iterates twice - unnecessary?
     // If there are zero nodes, the division will not be executed
-    let mean = (transforms_q
+    let mean = (nodes_q
         .iter()
-        .map(|(t, _)| t.translation.truncate())
+        .map(|(t, _, _)| t.translation.truncate())
         .sum::<Vec2>()
         .clamp_f32_range()
-        / transforms_q.iter().count() as f32)
+        / nodes_q.iter().count() as f32)
         .clamp_f32_range();
 
     let correction = center - mean;
+    let force = (correction / verlet.delta_secs_squared())
+        .clamp_f32_range()
+        .finite_or(Vec2::ZERO);
 
-    transforms_q
+    nodes_q
         .iter_mut()
-        .filter(|(_, mouse_locked)| mouse_locked.is_none())
-        .for_each(|(mut transform, _)| {
-            transform.translation =
-                (transform.translation + correction.extend(0.0)).clamp_f32_range();
-
-            #[cfg(debug_assertions)]
-            assert!(transform.is_finite(), "Not finite: {:?}", transform);
+        .filter(|(_, _, mouse_locked)| mouse_locked.is_none())
+        .for_each(|(_, mut accumulator, _)| {
+            accumulator.add(LABEL, force);
         });
 }
 
 #[cfg(test)]
 mod tests {
     use super::apply_mean_to_center;
-    use crate::force_directed_graph::common::{MouseLocked, NodePhysics};
+    use crate::force_directed_graph::{
+        common::{MouseLocked, NodePhysics},
+        verlet::{apply_forces, ForceScales, VerletRes},
+    };
     use bevy::app::{App, Update};
+    use bevy::ecs::schedule::IntoSystemConfigs as _;
     use bevy::math::{Vec2, Vec3};
     use bevy::transform::components::Transform;
 
-    #[test]
-    fn test_apply_mean_to_center() {
+    /// App which moves the nodes by the force within one update.
+    fn mean_to_center_app() -> App {
         let mut app = App::new();
+        let mut verlet = VerletRes::default();
+        verlet.update(1.0);
+        app.insert_resource(verlet)
+            .init_resource::<ForceScales>()
+            .add_systems(Update, (apply_mean_to_center, apply_forces).chain());
+        app
+    }
 
-        app.add_systems(Update, (apply_mean_to_center,));
+    #[test]
+    fn test_apply_mean_to_center() {
+        let mut app = mean_to_center_app();
 
         let transforms = [
             /* 2 | B
@@ -100,9 +124,7 @@ mod tests {
 
     #[test]
     fn test_apply_mean_to_center_mouse_locked() {
-        let mut app = App::new();
-
-        app.add_systems(Update, (apply_mean_to_center,));
+        let mut app = mean_to_center_app();
 
         let transform1 = Transform::from_translation(Vec3::new(9.0, 8.0, 111.0));
         let transform2_mouse_locked = Transform::from_translation(Vec3::new(1.0, 2.0, 222.0));
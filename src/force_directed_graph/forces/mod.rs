@@ -1,9 +1,14 @@
+pub mod collision;
+pub mod custom;
 pub mod cutoff_force;
 pub mod friction;
+pub mod fruchterman_reingold;
 pub mod galaxy;
 pub mod gravity;
 pub mod initial_velocity;
+pub mod layer_target;
 pub mod link;
 pub mod mean_to_center;
+pub mod pinned;
 pub mod repulsion;
 pub mod window_border;
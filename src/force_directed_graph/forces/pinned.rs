@@ -0,0 +1,17 @@
+use crate::force_directed_graph::common::{NodePhysics, Pinned};
+use bevy::{ecs::system::Query, transform::components::Transform};
+
+/// Label of the constraint in the
+/// [ForceDisplacements](crate::force_directed_graph::force_debug::ForceDisplacements).
+pub const LABEL: &str = "pinned";
+
+/// This is not really a force. It moves pinned nodes back to their pinned
+/// position after all forces have been applied and stops them. Thus, pinned
+/// nodes still act on other nodes (e.g. via links), but are not moved
+/// themselves.
+pub fn apply_pinned(mut transforms_q: Query<(&mut Transform, &mut NodePhysics, &Pinned)>) {
+    for (mut transform, mut node_physics, pinned) in &mut transforms_q {
+        transform.translation = pinned.position.extend(transform.translation.z);
+        node_physics.previous_position = pinned.position;
+    }
+}
@@ -1,29 +1,29 @@
 use crate::force_directed_graph::{
-    common::{MouseLocked, NodePhysics},
+    common::{ForceAccumulator, MouseLocked, NodePhysics},
     utils::{ClampF32Range, FiniteOrRandom as _},
-    verlet::VerletRes,
 };
 use bevy::{
-    ecs::{
-        query::With,
-        system::{Query, Res},
-    },
+    ecs::{query::With, system::Query},
     transform::components::Transform,
 };
 
+/// Label of the force in the [ForceAccumulator].
+pub const LABEL: &str = "repulsion";
+
 /// Add a repulsion force.
 /// * `strength` - force in kg*px/s^2; decreses with distance (1 / r^2)
 pub fn apply_repulsion_force(
     strength: f32,
-) -> impl Fn(
-    Query<'_, '_, (&mut Transform, std::option::Option<&MouseLocked>), With<NodePhysics>>,
-    Res<'_, VerletRes>,
-) {
-    move |mut transforms_q: Query<(&mut Transform, Option<&MouseLocked>), With<NodePhysics>>,
-          verlet: Res<VerletRes>| {
-        let mut combinations = transforms_q.iter_combinations_mut::<2>();
-        while let Some([(mut a_transform, a_mouse_locked), (mut b_transform, b_mouse_locked)]) =
-            combinations.fetch_next()
+) -> impl Fn(Query<'_, '_, (&Transform, &mut ForceAccumulator, Option<&MouseLocked>), With<NodePhysics>>)
+{
+    move |mut nodes_q: Query<
+        (&Transform, &mut ForceAccumulator, Option<&MouseLocked>),
+        With<NodePhysics>,
+    >| {
+        let mut combinations = nodes_q.iter_combinations_mut::<2>();
+        while let Some(
+            [(a_transform, mut a_accumulator, a_mouse_locked), (b_transform, mut b_accumulator, b_mouse_locked)],
+        ) = combinations.fetch_next()
         {
             let direction = (b_transform.translation.truncate()
                 - a_transform.translation.truncate())
@@ -38,24 +38,18 @@ pub fn apply_repulsion_force(
             let direction = direction.normalize().finite_or_random_normalized();
 
             // Calculate the repulsion based on the distance
-            let force = (((verlet.delta_secs_squared() * strength).clamp_f32_range()
-                / (distance * distance).clamp_f32_range())
-            .clamp_f32_range()
+            let force = ((strength / (distance * distance).clamp_f32_range()).clamp_f32_range()
                 * direction)
                 .clamp_f32_range();
+            #[cfg(debug_assertions)]
+            assert!(force.is_finite(), "Not finite: {:?}", force);
 
-            // Update the positions of both nodes
+            // Push both nodes apart
             if a_mouse_locked.is_none() {
-                a_transform.translation =
-                    (a_transform.translation - force.extend(0.0)).clamp_f32_range();
-                #[cfg(debug_assertions)]
-                assert!(a_transform.is_finite(), "Not finite: {:?}", a_transform);
+                a_accumulator.add(LABEL, -force);
             }
             if b_mouse_locked.is_none() {
-                b_transform.translation =
-                    (b_transform.translation + force.extend(0.0)).clamp_f32_range();
-                #[cfg(debug_assertions)]
-                assert!(b_transform.is_finite(), "Not finite: {:?}", b_transform);
+                b_accumulator.add(LABEL, force);
             }
         }
     }
@@ -1,39 +1,65 @@
 use crate::force_directed_graph::{
-    common::{MouseLocked, NodePhysics},
+    common::{MouseLocked, NodePhysics, NodeRadius},
+    node_shape::NodeShape,
     utils::ClampF32Range,
 };
 use bevy::{
     ecs::system::Query,
+    math::Vec2,
     render::camera::{Camera, OrthographicProjection},
     transform::components::{GlobalTransform, Transform},
 };
 
+/// Label of the constraint in the
+/// [ForceDisplacements](crate::force_directed_graph::force_debug::ForceDisplacements).
+pub const LABEL: &str = "window border";
+
 /// This is not really a force. It shifts all nodes inside the visible area of
-/// the window.
+/// the window. Nodes are kept inside entirely, according to their
+/// [NodeRadius] and [NodeShape].
 ///
 /// * `bounce` - how much velocity is conserved when bouncing off the walls. 0.0
 ///   for no bounce whatsoever, 1.0 for full bounce.
 pub fn apply_window_border(
     bounce: f32,
 ) -> impl Fn(
-    Query<'_, '_, (&mut Transform, &mut NodePhysics, Option<&MouseLocked>)>,
+    Query<
+        '_,
+        '_,
+        (
+            &mut Transform,
+            &mut NodePhysics,
+            Option<&NodeRadius>,
+            Option<&NodeShape>,
+            Option<&MouseLocked>,
+        ),
+    >,
     Query<'_, '_, (&Camera, &OrthographicProjection, &GlobalTransform)>,
 ) {
-    move |mut transforms_q: Query<(&mut Transform, &mut NodePhysics, Option<&MouseLocked>)>,
+    move |mut transforms_q: Query<(
+        &mut Transform,
+        &mut NodePhysics,
+        Option<&NodeRadius>,
+        Option<&NodeShape>,
+        Option<&MouseLocked>,
+    )>,
           camera_q: Query<(&Camera, &OrthographicProjection, &GlobalTransform)>| {
-        let margin = 30.0;
-
         let (_camera, projection, transform) = camera_q.get_single().unwrap();
-        let half_width = (projection.area.width() - margin).max(1.0) / 2.0;
-        let half_height = (projection.area.height() - margin).max(1.0) / 2.0;
         let camera_x = transform.translation().x;
         let camera_y = transform.translation().y;
-        let x_min = (camera_x - half_width).clamp_f32_range();
-        let x_max = (camera_x + half_width).clamp_f32_range();
-        let y_min = (camera_y - half_height).clamp_f32_range();
-        let y_max = (camera_y + half_height).clamp_f32_range();
 
-        for (mut transform, mut node_physics, _mouse_locked) in &mut transforms_q {
+        for (mut transform, mut node_physics, radius, shape, _mouse_locked) in &mut transforms_q {
+            let radius = radius.copied().unwrap_or_default().0;
+            let shape = shape.unwrap_or(&NodeShape::Circle);
+            let margin_x = 2.0 * shape.boundary_distance(radius, Vec2::X);
+            let margin_y = 2.0 * shape.boundary_distance(radius, Vec2::Y);
+            let half_width = (projection.area.width() - margin_x).max(1.0) / 2.0;
+            let half_height = (projection.area.height() - margin_y).max(1.0) / 2.0;
+            let x_min = (camera_x - half_width).clamp_f32_range();
+            let x_max = (camera_x + half_width).clamp_f32_range();
+            let y_min = (camera_y - half_height).clamp_f32_range();
+            let y_max = (camera_y + half_height).clamp_f32_range();
+
             //transform.translation.x = transform.translation.x.clamp(x_min, x_max);
             //transform.translation.y = transform.translation.y.clamp(y_min, y_max);
             //node_physics.previous_position.x = node_physics.previous_position.x.clamp(x_min, x_max);
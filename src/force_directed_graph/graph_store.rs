@@ -0,0 +1,450 @@
+use std::collections::VecDeque;
+
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::Assets,
+    color::Color,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{Event, EventReader, EventWriter},
+        observer::Trigger,
+        query::{Has, With},
+        system::{Commands, Local, Query, Res, ResMut, Resource, SystemParam},
+    },
+    hierarchy::DespawnRecursiveExt as _,
+    math::Vec2,
+    picking::{
+        events::{Click, Pointer},
+        pointer::PointerButton,
+    },
+    render::mesh::Mesh,
+    time::Time,
+    transform::components::Transform,
+    utils::{HashMap, HashSet},
+};
+
+use super::{
+    common::{LinkWeight, MouseLocked, NodeId, NodePhysics, NodeRadius},
+    hidden::{AllLinks, HiddenNode, ShowNode},
+    node_shape::NodeShape,
+    spawn_link, spawn_node,
+};
+
+/// Golden angle in radians. Consecutive nodes placed around a node at multiples
+/// of it are spread evenly, however many there are.
+const GOLDEN_ANGLE: f32 = 2.399_963;
+
+/// A node of the [GraphStore], which may or may not be spawned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredNode {
+    /// See [NodeId]
+    pub id: String,
+    /// See [crate::force_directed_graph::common::NodeLabel]
+    pub label: String,
+    /// See [crate::force_directed_graph::common::NodeMetadata]
+    pub metadata: Vec<(String, String)>,
+    pub color: Color,
+    pub shape: NodeShape,
+}
+
+/// A link of the [GraphStore], referring to its nodes by id.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredLink {
+    pub source: String,
+    pub target: String,
+    pub target_distance: f32,
+    /// See [LinkWeight]
+    pub weight: f32,
+}
+
+/// The whole graph, independent of the ECS. Graphs which are too large to be
+/// rendered are kept here and only parts of them are spawned as entities, see
+/// [GraphStorePlugin].
+#[derive(Resource, Debug, Default, Clone)]
+pub struct GraphStore {
+    pub nodes: Vec<StoredNode>,
+    pub links: Vec<StoredLink>,
+    /// Index into `nodes` per node id
+    indices: HashMap<String, usize>,
+    /// Indices into `links` per node id
+    adjacency: HashMap<String, Vec<usize>>,
+}
+
+impl GraphStore {
+    /// Add a node. A node with the same id is replaced.
+    pub fn add_node(&mut self, node: StoredNode) {
+        match self.indices.get(&node.id) {
+            Some(&index) => self.nodes[index] = node,
+            None => {
+                self.indices.insert(node.id.clone(), self.nodes.len());
+                self.nodes.push(node);
+            }
+        }
+    }
+
+    pub fn add_link(&mut self, link: StoredLink) {
+        let index = self.links.len();
+        self.adjacency
+            .entry(link.source.clone())
+            .or_default()
+            .push(index);
+        if link.target != link.source {
+            self.adjacency
+                .entry(link.target.clone())
+                .or_default()
+                .push(index);
+        }
+        self.links.push(link);
+    }
+
+    /// Remove a node and all links from or to it.
+    pub fn remove_node(&mut self, id: &str) -> Option<StoredNode> {
+        let index = self.indices.remove(id)?;
+        let node = self.nodes.remove(index);
+        self.links
+            .retain(|link| link.source != id && link.target != id);
+
+        // Indices after the removed node and link have shifted
+        let nodes = std::mem::take(&mut self.nodes);
+        let links = std::mem::take(&mut self.links);
+        *self = Self::default();
+        nodes.into_iter().for_each(|node| self.add_node(node));
+        links.into_iter().for_each(|link| self.add_link(link));
+        Some(node)
+    }
+
+    pub fn node(&self, id: &str) -> Option<&StoredNode> {
+        self.indices.get(id).map(|&index| &self.nodes[index])
+    }
+
+    /// All links from or to the given node.
+    pub fn links_of<'a>(&'a self, id: &str) -> impl Iterator<Item = &'a StoredLink> + 'a {
+        self.adjacency
+            .get(id)
+            .into_iter()
+            .flatten()
+            .map(|&index| &self.links[index])
+    }
+
+    /// Ids of all nodes linked to the given node in either direction, without
+    /// duplicates.
+    pub fn neighbors(&self, id: &str) -> Vec<&str> {
+        let mut neighbors = Vec::new();
+        for link in self.links_of(id) {
+            let neighbor = if link.source == id {
+                &link.target
+            } else {
+                &link.source
+            };
+            if neighbor != id && !neighbors.contains(&neighbor.as_str()) {
+                neighbors.push(neighbor.as_str());
+            }
+        }
+        neighbors
+    }
+}
+
+/// Component for nodes which were spawned by expanding another node. They are
+/// despawned again when that node is collapsed.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct ExpandedFrom(pub Entity);
+
+/// Event to spawn the neighbors of a node from the [GraphStore]. Neighbors
+/// which are already spawned but hidden are shown.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct ExpandNode(pub Entity);
+
+/// Event to despawn all nodes which were spawned by expanding the node, see
+/// [ExpandedFrom].
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct CollapseNode(pub Entity);
+
+/// Time between two clicks to count as double-click in s.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct DoubleClickSettings {
+    pub max_interval: f32,
+}
+
+/// Detection of double-clicks on nodes, see [DoubleClickSettings].
+#[derive(SystemParam)]
+pub struct DoubleClick<'w, 's> {
+    settings: Res<'w, DoubleClickSettings>,
+    time: Res<'w, Time>,
+    /// Node and point of time of the last click which was not a double-click
+    last_click: Local<'s, Option<(Entity, f32)>>,
+}
+
+impl DoubleClick<'_, '_> {
+    /// Register a click on `entity`. Returns whether it is a double-click.
+    pub fn click(&mut self, entity: Entity) -> bool {
+        let now = self.time.elapsed_secs();
+        let max_interval = self.settings.max_interval;
+        let is_double_click = self.last_click.is_some_and(|(last_entity, last_time)| {
+            last_entity == entity && now - last_time <= max_interval
+        });
+        *self.last_click = if is_double_click {
+            None
+        } else {
+            Some((entity, now))
+        };
+        is_double_click
+    }
+}
+
+/// Plugin for exploring a [GraphStore] by spawning parts of it ("progressive
+/// disclosure"). Double-clicking a node (see [toggle_on_double_click])
+/// expands it if any of its neighbors are not shown yet and collapses it
+/// otherwise. Alternatively, send [ExpandNode]/[CollapseNode] events.
+pub struct GraphStorePlugin {
+    /// Time between two clicks to count as double-click in s
+    pub double_click_interval: f32,
+}
+
+impl Default for GraphStorePlugin {
+    fn default() -> Self {
+        Self {
+            double_click_interval: 0.3,
+        }
+    }
+}
+
+impl Plugin for GraphStorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GraphStore>()
+            .insert_resource(DoubleClickSettings {
+                max_interval: self.double_click_interval,
+            })
+            .add_event::<ExpandNode>()
+            .add_event::<CollapseNode>()
+            .add_event::<ShowNode>()
+            .add_systems(Update, (expand_nodes, collapse_nodes));
+    }
+}
+
+/// Position of the `i`-th node placed around `center`.
+pub fn expansion_position(center: Vec2, i: usize, distance: f32) -> Vec2 {
+    center + Vec2::from_angle(i as f32 * GOLDEN_ANGLE) * distance
+}
+
+/// Observer for click events on nodes. Sends an [ExpandNode] event on
+/// double-click, or a [CollapseNode] event if all neighbors are shown already.
+pub fn toggle_on_double_click(
+    trigger: Trigger<Pointer<Click>>,
+    nodes_q: Query<&NodeId, With<NodePhysics>>,
+    locked_q: Query<(), With<MouseLocked>>,
+    store: Res<GraphStore>,
+    mut double_click: DoubleClick,
+    mut expand_events: EventWriter<ExpandNode>,
+    mut collapse_events: EventWriter<CollapseNode>,
+) {
+    if trigger.event().button != PointerButton::Primary {
+        return;
+    }
+    let entity = trigger.entity();
+    // The click ends a drag-and-drop
+    if locked_q.contains(entity) {
+        return;
+    }
+    if !double_click.click(entity) {
+        return;
+    }
+
+    let Ok(id) = nodes_q.get(entity) else {
+        return;
+    };
+    let shown = nodes_q
+        .iter()
+        .map(|id| id.0.as_str())
+        .collect::<HashSet<_>>();
+    let expanded = store
+        .neighbors(&id.0)
+        .iter()
+        .all(|neighbor| shown.contains(neighbor));
+    if expanded {
+        collapse_events.send(CollapseNode(entity));
+    } else {
+        expand_events.send(ExpandNode(entity));
+    }
+}
+
+/// Spawn the neighbors of a node and the links to them on [ExpandNode] events.
+pub fn expand_nodes(
+    mut expand_events: EventReader<ExpandNode>,
+    nodes_q: Query<(Entity, &NodeId, &Transform, Has<HiddenNode>)>,
+    links: AllLinks,
+    store: Res<GraphStore>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut show_events: EventWriter<ShowNode>,
+    mut commands: Commands,
+) {
+    let mut entities = nodes_q
+        .iter()
+        .map(|(entity, id, _, _)| (id.0.clone(), entity))
+        .collect::<HashMap<_, _>>();
+    let mut spawned_links = links
+        .iter()
+        .map(|(_, link)| (link.source, link.target))
+        .collect::<HashSet<_>>();
+
+    for ExpandNode(entity) in expand_events.read() {
+        let Ok((_, id, transform, _)) = nodes_q.get(*entity) else {
+            continue;
+        };
+        let center = transform.translation.truncate();
+
+        for (i, link) in store.links_of(&id.0).enumerate() {
+            let neighbor_id = if link.source == id.0 {
+                &link.target
+            } else {
+                &link.source
+            };
+            match entities.get(neighbor_id) {
+                Some(&neighbor) => {
+                    if nodes_q.get(neighbor).is_ok_and(|(_, _, _, hidden)| hidden) {
+                        show_events.send(ShowNode(neighbor));
+                    }
+                }
+                None => {
+                    let Some(node) = store.node(neighbor_id) else {
+                        continue;
+                    };
+                    let position = expansion_position(center, i, link.target_distance);
+                    let neighbor = spawn_node(
+                        &mut commands,
+                        &mut meshes,
+                        node,
+                        NodeRadius::default(),
+                        Transform::from_translation(position.extend(transform.translation.z)),
+                    );
+                    commands.entity(neighbor).insert(ExpandedFrom(*entity));
+                    entities.insert(neighbor_id.clone(), neighbor);
+                }
+            }
+
+            // All links between the neighbor and spawned nodes
+            for neighbor_link in store.links_of(neighbor_id) {
+                let (Some(&source), Some(&target)) = (
+                    entities.get(&neighbor_link.source),
+                    entities.get(&neighbor_link.target),
+                ) else {
+                    continue;
+                };
+                if source == target || !spawned_links.insert((source, target)) {
+                    continue;
+                }
+                spawn_link(
+                    &mut commands,
+                    &mut meshes,
+                    source,
+                    target,
+                    neighbor_link.target_distance,
+                    LinkWeight(neighbor_link.weight),
+                );
+            }
+        }
+    }
+}
+
+/// Despawn all nodes which were spawned by expanding a node (recursively) and
+/// their links on [CollapseNode] events.
+pub fn collapse_nodes(
+    mut collapse_events: EventReader<CollapseNode>,
+    expanded_q: Query<(Entity, &ExpandedFrom)>,
+    links: AllLinks,
+    mut commands: Commands,
+) {
+    for CollapseNode(entity) in collapse_events.read() {
+        let mut children = HashMap::<Entity, Vec<Entity>>::default();
+        for (child, ExpandedFrom(parent)) in &expanded_q {
+            children.entry(*parent).or_default().push(child);
+        }
+
+        let mut collapsed = HashSet::<Entity>::default();
+        let mut queue = VecDeque::from([*entity]);
+        while let Some(parent) = queue.pop_front() {
+            for &child in children.get(&parent).into_iter().flatten() {
+                if child != *entity && collapsed.insert(child) {
+                    queue.push_back(child);
+                }
+            }
+        }
+
+        for (link_entity, link) in links.iter() {
+            if collapsed.contains(&link.source) || collapsed.contains(&link.target) {
+                commands.entity(link_entity).despawn_recursive();
+            }
+        }
+        for entity in collapsed {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GraphStore, StoredLink, StoredNode};
+    use crate::force_directed_graph::node_shape::NodeShape;
+    use bevy::color::Color;
+
+    #[test]
+    fn test_neighbors() {
+        let mut store = GraphStore::default();
+        for id in ["a", "b", "c", "d"] {
+            store.add_node(StoredNode {
+                id: id.to_string(),
+                label: id.to_string(),
+                metadata: Vec::new(),
+                color: Color::WHITE,
+                shape: NodeShape::Circle,
+            });
+        }
+        for (source, target) in [("a", "b"), ("c", "a"), ("a", "b"), ("a", "a"), ("c", "d")] {
+            store.add_link(StoredLink {
+                source: source.to_string(),
+                target: target.to_string(),
+                target_distance: 100.0,
+                weight: 1.0,
+            });
+        }
+
+        assert_eq!(store.neighbors("a"), vec!["b", "c"]);
+        assert_eq!(store.neighbors("d"), vec!["c"]);
+        assert_eq!(store.links_of("a").count(), 4);
+        assert!(store.neighbors("e").is_empty());
+        assert_eq!(store.node("c").map(|node| node.label.as_str()), Some("c"));
+    }
+
+    #[test]
+    fn test_remove_node() {
+        let mut store = GraphStore::default();
+        for id in ["a", "b", "c"] {
+            store.add_node(StoredNode {
+                id: id.to_string(),
+                label: id.to_string(),
+                metadata: Vec::new(),
+                color: Color::WHITE,
+                shape: NodeShape::Circle,
+            });
+        }
+        for (source, target) in [("a", "b"), ("b", "c"), ("c", "a")] {
+            store.add_link(StoredLink {
+                source: source.to_string(),
+                target: target.to_string(),
+                target_distance: 100.0,
+                weight: 1.0,
+            });
+        }
+
+        assert_eq!(
+            store.remove_node("a").map(|node| node.id),
+            Some("a".to_string())
+        );
+        assert_eq!(store.remove_node("a"), None);
+        assert!(store.node("a").is_none());
+        assert_eq!(store.node("c").map(|node| node.label.as_str()), Some("c"));
+        assert_eq!(store.neighbors("b"), vec!["c"]);
+        assert_eq!(store.neighbors("c"), vec!["b"]);
+        assert_eq!(store.links.len(), 1);
+    }
+}
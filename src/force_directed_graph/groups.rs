@@ -0,0 +1,545 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::Assets,
+    color::{Color, LinearRgba},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{Event, EventReader, EventWriter},
+        query::{Has, With, Without},
+        schedule::IntoSystemConfigs as _,
+        system::{Commands, Query, Res, ResMut},
+    },
+    hierarchy::DespawnRecursiveExt as _,
+    input::{keyboard::KeyCode, ButtonInput},
+    math::Vec2,
+    render::mesh::Mesh,
+    transform::components::Transform,
+    utils::{HashMap, HashSet},
+};
+
+use super::{
+    common::{LinkWeight, NodeColor, NodeId, NodeLabel, NodeLink, NodePhysics, NodeRadius},
+    graph_store::StoredNode,
+    hidden::{self, AllLinks, HiddenNode, HideNode, ShowNode},
+    node_shape::NodeShape,
+    selection::Selected,
+    spawn_link, spawn_node,
+};
+
+/// Name of the group a node belongs to, e.g. its module or package. All nodes
+/// of a group can be collapsed at once, see [GroupPlugin].
+#[derive(Component, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Group(pub String);
+
+/// Component for a node which represents a collapsed set of nodes. The members
+/// are hidden while the meta-node is shown. Meta-nodes have no [NodeId].
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct MetaNode {
+    /// Members with their position relative to the meta-node
+    pub members: Vec<(Entity, Vec2)>,
+    /// See [CollapseGroup::group]
+    pub group: Option<String>,
+}
+
+/// Component for links between a meta-node and an outside node, which replace
+/// all links between the members and that node. Their [LinkWeight] is the sum
+/// of the replaced links' weights.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct AggregatedLink {
+    /// Number of replaced links
+    pub count: usize,
+}
+
+/// Event to collapse nodes into a meta-node at their centroid.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct CollapseGroup {
+    pub members: Vec<Entity>,
+    pub label: String,
+    /// Name of the [Group] if all nodes of a group are collapsed
+    pub group: Option<String>,
+}
+
+/// Event to replace a meta-node by its members again.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct ExpandGroup(pub Entity);
+
+/// Plugin for collapsing nodes into meta-nodes and expanding them again, via
+/// [CollapseGroup]/[ExpandGroup] events or via keyboard:
+///
+/// * `G` - collapse the selected nodes. Without a selection, collapse every
+///   [Group]
+/// * `Shift+G` - expand the selected meta-nodes. Without a selection, expand
+///   all meta-nodes
+pub struct GroupPlugin;
+
+impl Plugin for GroupPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<hidden::HiddenPlugin>() {
+            app.add_plugins(hidden::HiddenPlugin);
+        }
+        app.add_event::<CollapseGroup>()
+            .add_event::<ExpandGroup>()
+            .add_systems(
+                Update,
+                (
+                    group_keyboard_input,
+                    (collapse_groups, expand_groups)
+                        .chain()
+                        .before(hidden::hide_and_show_nodes),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Radius of a meta-node, so that its area grows with the number of members.
+pub fn meta_node_radius(member_count: usize) -> NodeRadius {
+    NodeRadius(NodeRadius::default().0 * (member_count.max(1) as f32).sqrt())
+}
+
+/// Send [CollapseGroup]/[ExpandGroup] events on key presses. See [GroupPlugin].
+pub fn group_keyboard_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    nodes_q: Query<(Entity, Option<&NodeLabel>, Option<&Group>), With<NodePhysics>>,
+    selected_q: Query<Entity, With<Selected>>,
+    meta_nodes_q: Query<Entity, With<MetaNode>>,
+    mut collapse_events: EventWriter<CollapseGroup>,
+    mut expand_events: EventWriter<ExpandGroup>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyG) {
+        return;
+    }
+
+    if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        let meta_nodes = if selected_q.is_empty() {
+            meta_nodes_q.iter().collect::<Vec<_>>()
+        } else {
+            selected_q
+                .iter()
+                .filter(|entity| meta_nodes_q.contains(*entity))
+                .collect()
+        };
+        expand_events.send_batch(meta_nodes.into_iter().map(ExpandGroup));
+    } else if selected_q.is_empty() {
+        let mut groups = HashMap::<&str, Vec<Entity>>::default();
+        for (entity, _, group) in &nodes_q {
+            if let Some(Group(name)) = group {
+                groups.entry(name).or_default().push(entity);
+            }
+        }
+        collapse_events.send_batch(groups.into_iter().map(|(name, members)| CollapseGroup {
+            members,
+            label: name.to_string(),
+            group: Some(name.to_string()),
+        }));
+    } else {
+        let members = selected_q.iter().collect::<Vec<_>>();
+        let first_label = nodes_q
+            .get(members[0])
+            .ok()
+            .and_then(|(_, label, _)| label)
+            .map_or_else(|| members[0].to_string(), |label| label.0.clone());
+        let label = if members.len() > 1 {
+            format!("{} +{}", first_label, members.len() - 1)
+        } else {
+            first_label
+        };
+        collapse_events.send(CollapseGroup {
+            members,
+            label,
+            group: None,
+        });
+    }
+}
+
+/// Collapse nodes into meta-nodes on [CollapseGroup] events. The meta-node gets
+/// the mean color of its members and one link per outside node, see
+/// [AggregatedLink]. Links to members of other groups collapsed at the same
+/// time end at their meta-node.
+pub fn collapse_groups(
+    mut collapse_events: EventReader<CollapseGroup>,
+    nodes_q: Query<(&Transform, Option<&NodeColor>), With<NodePhysics>>,
+    links_q: Query<(&NodeLink, Option<&LinkWeight>), Without<NodePhysics>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut hide_events: EventWriter<HideNode>,
+    mut commands: Commands,
+) {
+    // Meta-node per collapsed node
+    let mut meta_nodes = HashMap::<Entity, Entity>::default();
+    for CollapseGroup {
+        members,
+        label,
+        group,
+    } in collapse_events.read()
+    {
+        let members = members
+            .iter()
+            .copied()
+            .filter(|member| nodes_q.contains(*member) && !meta_nodes.contains_key(member))
+            .collect::<Vec<_>>();
+        if members.is_empty() {
+            continue;
+        }
+
+        let mut centroid = Vec2::ZERO;
+        let mut z = 0.0_f32;
+        let mut color = LinearRgba::NONE;
+        for &member in &members {
+            let (transform, member_color) = nodes_q.get(member).unwrap();
+            centroid += transform.translation.truncate();
+            z = z.max(transform.translation.z);
+            color += member_color
+                .map_or(Color::WHITE, |color| color.0)
+                .to_linear();
+        }
+        centroid /= members.len() as f32;
+        color *= 1.0 / members.len() as f32;
+
+        let meta_node = spawn_node(
+            &mut commands,
+            &mut meshes,
+            &StoredNode {
+                id: String::new(),
+                label: label.clone(),
+                metadata: vec![("members".to_string(), members.len().to_string())],
+                color: color.into(),
+                shape: NodeShape::Circle,
+            },
+            meta_node_radius(members.len()),
+            Transform::from_translation(centroid.extend(z)),
+        );
+        commands
+            .entity(meta_node)
+            .insert(MetaNode {
+                members: members
+                    .iter()
+                    .map(|&member| {
+                        let (transform, _) = nodes_q.get(member).unwrap();
+                        (member, transform.translation.truncate() - centroid)
+                    })
+                    .collect(),
+                group: group.clone(),
+            })
+            // Meta-nodes are not part of the graph, e.g. of saved layouts
+            .remove::<NodeId>();
+        meta_nodes.extend(members.iter().map(|&member| (member, meta_node)));
+
+        hide_events.send_batch(members.into_iter().map(HideNode));
+    }
+
+    // Links from meta-nodes to outside nodes or other meta-nodes: (number of
+    // links, sum of target distances, sum of weights)
+    let mut aggregated = HashMap::<(Entity, Entity), (usize, f32, f32)>::default();
+    for (link, weight) in &links_q {
+        let source = meta_nodes.get(&link.source).copied();
+        let target = meta_nodes.get(&link.target).copied();
+        let key = match (source, target) {
+            (Some(source), Some(target)) if source == target => continue,
+            (Some(source), Some(target)) => (source.min(target), source.max(target)),
+            (Some(source), None) => (source, link.target),
+            (None, Some(target)) => (target, link.source),
+            (None, None) => continue,
+        };
+        let (count, distance, total_weight) = aggregated.entry(key).or_default();
+        *count += 1;
+        *distance += link.target_distance;
+        *total_weight += weight.copied().unwrap_or_default().0;
+    }
+    for ((meta_node, other), (count, distance, weight)) in aggregated {
+        let link = spawn_link(
+            &mut commands,
+            &mut meshes,
+            meta_node,
+            other,
+            distance / count as f32,
+            LinkWeight(weight),
+        );
+        commands.entity(link).insert(AggregatedLink { count });
+    }
+}
+
+/// Replace meta-nodes by their members on [ExpandGroup] events. The members
+/// keep their positions relative to each other, centered on the meta-node.
+///
+/// Links from the members to nodes which are still collapsed into another
+/// meta-node are aggregated again, so the members stay linked to that
+/// meta-node, see [AggregatedLink].
+pub fn expand_groups(
+    mut expand_events: EventReader<ExpandGroup>,
+    meta_nodes_q: Query<(Entity, &MetaNode, Has<HiddenNode>)>,
+    mut transforms_q: Query<(&mut Transform, Has<HiddenNode>)>,
+    links: AllLinks,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut show_events: EventWriter<ShowNode>,
+    mut commands: Commands,
+) {
+    let expanded = expand_events
+        .read()
+        .map(|ExpandGroup(meta_node)| *meta_node)
+        .filter(|meta_node| {
+            meta_nodes_q
+                .get(*meta_node)
+                .is_ok_and(|(.., hidden)| !hidden)
+        })
+        .collect::<HashSet<_>>();
+    if expanded.is_empty() {
+        return;
+    }
+
+    let mut shown = HashSet::<Entity>::default();
+    for &meta_node in &expanded {
+        let (_, MetaNode { members, .. }, _) = meta_nodes_q.get(meta_node).unwrap();
+        let center = transforms_q
+            .get(meta_node)
+            .unwrap()
+            .0
+            .translation
+            .truncate();
+
+        for &(member, offset) in members {
+            let Ok((mut member_transform, true)) = transforms_q.get_mut(member) else {
+                continue;
+            };
+            member_transform.translation = (center + offset).extend(member_transform.translation.z);
+            show_events.send(ShowNode(member));
+            shown.insert(member);
+        }
+
+        for (link_entity, link) in links.iter() {
+            if link.source == meta_node || link.target == meta_node {
+                commands.entity(link_entity).despawn_recursive();
+            }
+        }
+        commands.entity(meta_node).despawn_recursive();
+    }
+
+    // Meta-node per collapsed node, for all meta-nodes which stay collapsed
+    let holders = meta_nodes_q
+        .iter()
+        .filter(|(meta_node, ..)| !expanded.contains(meta_node))
+        .flat_map(|(meta_node, MetaNode { members, .. }, _)| {
+            members.iter().map(move |&(member, _)| (member, meta_node))
+        })
+        .collect::<HashMap<_, _>>();
+    // The outermost meta-node, if meta-nodes were collapsed again
+    let holder = |mut node: Entity| {
+        let mut holder = None;
+        while let Some(&meta_node) = holders.get(&node) {
+            holder = Some(meta_node);
+            node = meta_node;
+        }
+        holder
+    };
+
+    // Links from shown members to meta-nodes: (number of links, sum of target
+    // distances, sum of weights). Links which already end at a meta-node, e.g.
+    // as it was collapsed after the member, are shown again as they are.
+    let mut aggregated = HashMap::<(Entity, Entity), (usize, f32, f32)>::default();
+    let mut existing = HashSet::<(Entity, Entity)>::default();
+    for (link_entity, link) in links.iter() {
+        let (member, other) = match (shown.contains(&link.source), shown.contains(&link.target)) {
+            (true, false) => (link.source, link.target),
+            (false, true) => (link.target, link.source),
+            _ => continue,
+        };
+        if meta_nodes_q.contains(other) {
+            existing.insert((member, other));
+            continue;
+        }
+        let Some(meta_node) = holder(other) else {
+            continue;
+        };
+        let (count, distance, total_weight) = aggregated.entry((member, meta_node)).or_default();
+        *count += 1;
+        *distance += link.target_distance;
+        *total_weight += links.weight(link_entity).unwrap_or_default().0;
+    }
+    for ((member, meta_node), (count, distance, weight)) in aggregated {
+        if existing.contains(&(member, meta_node)) {
+            continue;
+        }
+        let link = spawn_link(
+            &mut commands,
+            &mut meshes,
+            member,
+            meta_node,
+            distance / count as f32,
+            LinkWeight(weight),
+        );
+        commands.entity(link).insert(AggregatedLink { count });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        collapse_groups, expand_groups, meta_node_radius, AggregatedLink, CollapseGroup,
+        ExpandGroup, MetaNode,
+    };
+    use crate::force_directed_graph::{
+        common::{NodeId, NodeLink, NodePhysics, NodeRadius},
+        hidden::{self, HiddenPlugin, HideNode},
+    };
+    use bevy::{
+        app::{App, Update},
+        asset::Assets,
+        ecs::{entity::Entity, schedule::IntoSystemConfigs as _},
+        render::mesh::Mesh,
+        sprite::ColorMaterial,
+        transform::components::Transform,
+    };
+
+    #[test]
+    fn test_meta_node_radius() {
+        let radius = NodeRadius::default().0;
+        assert_eq!(meta_node_radius(1), NodeRadius(radius));
+        assert_eq!(meta_node_radius(4), NodeRadius(2.0 * radius));
+        assert_eq!(meta_node_radius(0), NodeRadius(radius));
+    }
+
+    #[test]
+    fn test_collapse_groups_at_once() {
+        let mut app = App::new();
+        app.init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .add_event::<CollapseGroup>()
+            .add_event::<HideNode>()
+            .add_systems(Update, collapse_groups);
+        let mut spawn_node = |id: &str| {
+            app.world_mut()
+                .spawn((
+                    NodeId(id.to_string()),
+                    Transform::default(),
+                    NodePhysics::from_transform(Transform::default()),
+                ))
+                .id()
+        };
+        let (a1, a2, b, c) = (
+            spawn_node("a1"),
+            spawn_node("a2"),
+            spawn_node("b"),
+            spawn_node("c"),
+        );
+        for (source, target) in [(a1, a2), (a1, b), (b, a2), (a1, c)] {
+            app.world_mut().spawn(NodeLink {
+                source,
+                target,
+                target_distance: 100.0,
+            });
+        }
+
+        app.world_mut().send_event(CollapseGroup {
+            members: vec![a1, a2],
+            label: "a".to_string(),
+            group: Some("a".to_string()),
+        });
+        app.world_mut().send_event(CollapseGroup {
+            members: vec![b],
+            label: "b".to_string(),
+            group: Some("b".to_string()),
+        });
+        app.update();
+
+        let mut meta_nodes_q = app.world_mut().query::<(Entity, &MetaNode)>();
+        let mut meta_node = |group: &str| {
+            meta_nodes_q
+                .iter(app.world())
+                .find(|(_, meta_node)| meta_node.group.as_deref() == Some(group))
+                .map(|(entity, _)| entity)
+                .unwrap()
+        };
+        let (meta_a, meta_b) = (meta_node("a"), meta_node("b"));
+        assert!(app.world().get::<NodeId>(meta_a).is_none());
+
+        // Undirected, sorted by entity
+        let ends = |a: Entity, b: Entity| if a < b { (a, b) } else { (b, a) };
+        let mut links_q = app.world_mut().query::<(&NodeLink, &AggregatedLink)>();
+        let mut links = links_q
+            .iter(app.world())
+            .map(|(link, aggregated)| (ends(link.source, link.target), aggregated.count))
+            .collect::<Vec<_>>();
+        links.sort();
+        let mut expected = vec![(ends(meta_a, meta_b), 2), (ends(meta_a, c), 1)];
+        expected.sort();
+        assert_eq!(links, expected);
+    }
+
+    #[test]
+    fn test_expand_one_of_two_groups() {
+        let mut app = App::new();
+        app.init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .add_plugins(HiddenPlugin)
+            .add_event::<CollapseGroup>()
+            .add_event::<ExpandGroup>()
+            .add_systems(
+                Update,
+                (collapse_groups, expand_groups)
+                    .chain()
+                    .before(hidden::hide_and_show_nodes),
+            );
+        let mut spawn_node = |id: &str| {
+            app.world_mut()
+                .spawn((
+                    NodeId(id.to_string()),
+                    Transform::default(),
+                    NodePhysics::from_transform(Transform::default()),
+                ))
+                .id()
+        };
+        let (a1, a2, b1, b2) = (
+            spawn_node("a1"),
+            spawn_node("a2"),
+            spawn_node("b1"),
+            spawn_node("b2"),
+        );
+        for (source, target) in [(a1, a2), (a1, b1), (b2, a1), (a2, b1)] {
+            app.world_mut().spawn(NodeLink {
+                source,
+                target,
+                target_distance: 100.0,
+            });
+        }
+
+        for (name, members) in [("a", vec![a1, a2]), ("b", vec![b1, b2])] {
+            app.world_mut().send_event(CollapseGroup {
+                members,
+                label: name.to_string(),
+                group: Some(name.to_string()),
+            });
+        }
+        app.update();
+        let mut meta_nodes_q = app.world_mut().query::<(Entity, &MetaNode)>();
+        let mut meta_node = |group: &str| {
+            meta_nodes_q
+                .iter(app.world())
+                .find(|(_, meta_node)| meta_node.group.as_deref() == Some(group))
+                .map(|(entity, _)| entity)
+                .unwrap()
+        };
+        let (meta_a, meta_b) = (meta_node("a"), meta_node("b"));
+
+        app.world_mut().send_event(ExpandGroup(meta_a));
+        app.update();
+
+        assert!(app.world().get_entity(meta_a).is_err());
+        let mut links_q = app
+            .world_mut()
+            .query::<(&NodeLink, Option<&AggregatedLink>)>();
+        let mut links = links_q
+            .iter(app.world())
+            .map(|(link, aggregated)| {
+                (
+                    link.source,
+                    link.target,
+                    aggregated.map(|aggregated| aggregated.count),
+                )
+            })
+            .collect::<Vec<_>>();
+        links.sort();
+        let mut expected = vec![(a1, a2, None), (a1, meta_b, Some(2)), (a2, meta_b, Some(1))];
+        expected.sort();
+        assert_eq!(links, expected);
+    }
+}
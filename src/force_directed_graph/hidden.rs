@@ -0,0 +1,140 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{Event, EventReader},
+        query::{With, Without},
+        schedule::IntoSystemConfigs as _,
+        system::{Commands, Query, SystemParam},
+    },
+    render::view::Visibility,
+    transform::components::Transform,
+    utils::HashSet,
+};
+
+use super::{
+    common::{LinkWeight, MouseLocked, NodeLink, NodePhysics},
+    selection::Selected,
+    tween::Tween,
+    SimulationSet,
+};
+
+/// Marker component for hidden nodes. Hidden nodes are neither rendered nor
+/// simulated, since their [NodePhysics] is removed.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct HiddenNode;
+
+/// Component for links with at least one hidden node. The [NodeLink] is moved
+/// into this component, so forces ignore the link until both nodes are shown
+/// again.
+#[derive(Component, Debug, Clone)]
+pub struct HiddenLink(pub NodeLink);
+
+/// All links, shown ones as well as hidden ones.
+#[derive(SystemParam)]
+pub struct AllLinks<'w, 's> {
+    links_q: Query<'w, 's, (Entity, &'static NodeLink)>,
+    hidden_links_q: Query<'w, 's, (Entity, &'static HiddenLink)>,
+    weights_q: Query<'w, 's, &'static LinkWeight>,
+}
+
+impl AllLinks<'_, '_> {
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &NodeLink)> {
+        self.links_q.iter().chain(
+            self.hidden_links_q
+                .iter()
+                .map(|(entity, HiddenLink(link))| (entity, link)),
+        )
+    }
+
+    pub fn weight(&self, link: Entity) -> Option<LinkWeight> {
+        self.weights_q.get(link).ok().copied()
+    }
+}
+
+/// Event to hide a node and its links.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct HideNode(pub Entity);
+
+/// Event to show a hidden node again. Its links are shown as soon as both of
+/// their nodes are visible.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct ShowNode(pub Entity);
+
+/// Plugin for hiding and showing nodes via [HideNode]/[ShowNode] events.
+pub struct HiddenPlugin;
+
+impl Plugin for HiddenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HideNode>()
+            .add_event::<ShowNode>()
+            .add_systems(Update, hide_and_show_nodes.before(SimulationSet::Forces));
+    }
+}
+
+/// Hide and show nodes and their links on [HideNode]/[ShowNode] events. Shown
+/// nodes are handed back to the simulation at rest.
+pub fn hide_and_show_nodes(
+    mut hide_events: EventReader<HideNode>,
+    mut show_events: EventReader<ShowNode>,
+    nodes_q: Query<Entity, With<NodePhysics>>,
+    hidden_nodes_q: Query<&Transform, With<HiddenNode>>,
+    links_q: Query<(Entity, &NodeLink), Without<NodePhysics>>,
+    hidden_links_q: Query<(Entity, &HiddenLink)>,
+    mut commands: Commands,
+) {
+    let hide = hide_events
+        .read()
+        .map(|HideNode(entity)| *entity)
+        .filter(|entity| nodes_q.contains(*entity))
+        .collect::<HashSet<_>>();
+    let show = show_events
+        .read()
+        .map(|ShowNode(entity)| *entity)
+        .filter(|entity| hidden_nodes_q.contains(*entity) && !hide.contains(entity))
+        .collect::<HashSet<_>>();
+    if hide.is_empty() && show.is_empty() {
+        return;
+    }
+
+    for &entity in &hide {
+        commands
+            .entity(entity)
+            .insert((HiddenNode, Visibility::Hidden))
+            .remove::<(NodePhysics, MouseLocked, Tween, Selected)>();
+    }
+    for &entity in &show {
+        let Ok(transform) = hidden_nodes_q.get(entity) else {
+            continue;
+        };
+        commands
+            .entity(entity)
+            .insert((
+                NodePhysics::from_transform(*transform),
+                Visibility::Inherited,
+            ))
+            .remove::<HiddenNode>();
+    }
+
+    // Whether a node is hidden once the commands are applied
+    let is_hidden = |entity: Entity| {
+        hide.contains(&entity) || (hidden_nodes_q.contains(entity) && !show.contains(&entity))
+    };
+    for (entity, link) in &links_q {
+        if is_hidden(link.source) || is_hidden(link.target) {
+            commands
+                .entity(entity)
+                .insert((HiddenLink(link.clone()), Visibility::Hidden))
+                .remove::<NodeLink>();
+        }
+    }
+    for (entity, HiddenLink(link)) in &hidden_links_q {
+        if !is_hidden(link.source) && !is_hidden(link.target) {
+            commands
+                .entity(entity)
+                .insert((link.clone(), Visibility::Inherited))
+                .remove::<HiddenLink>();
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::VecDeque;
+
+use bevy::{
+    app::{App, Plugin},
+    color::Alpha as _,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        observer::Trigger,
+        query::{With, Without},
+        system::{Commands, Query, Res, Resource},
+    },
+    picking::events::{Out, Over, Pointer},
+    utils::{HashMap, HashSet},
+};
+
+use super::common::{LinkColor, NodeColor, NodeLink, NodePhysics};
+
+/// How the neighborhood of a hovered node is highlighted.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct HoverSettings {
+    /// Nodes up to this many links away from the hovered node are part of its
+    /// neighborhood
+    pub depth: usize,
+    /// Alpha of nodes and links outside of the neighborhood is multiplied by
+    /// this factor
+    pub dimmed_alpha: f32,
+}
+
+/// Component for nodes and links whose alpha was changed to highlight a
+/// neighborhood. Saves the alpha to restore afterwards.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct HoverHighlight {
+    pub original_alpha: f32,
+}
+
+/// Plugin for highlighting the neighborhood of the hovered node, see
+/// [highlight_neighborhood]. The observers need to be added to every node.
+pub struct HoverPlugin {
+    pub depth: usize,
+    pub dimmed_alpha: f32,
+}
+
+impl Default for HoverPlugin {
+    fn default() -> Self {
+        Self {
+            depth: 1,
+            dimmed_alpha: 0.15,
+        }
+    }
+}
+
+impl Plugin for HoverPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HoverSettings {
+            depth: self.depth,
+            dimmed_alpha: self.dimmed_alpha,
+        });
+    }
+}
+
+/// All nodes at most `depth` links away from `root`, including `root`.
+pub fn neighborhood(
+    root: Entity,
+    links: impl IntoIterator<Item = (Entity, Entity)>,
+    depth: usize,
+) -> HashSet<Entity> {
+    let mut neighbors = HashMap::<Entity, Vec<Entity>>::default();
+    for (source, target) in links {
+        neighbors.entry(source).or_default().push(target);
+        neighbors.entry(target).or_default().push(source);
+    }
+
+    let mut visited = HashSet::from_iter([root]);
+    let mut queue = VecDeque::from([(root, 0)]);
+    while let Some((node, distance)) = queue.pop_front() {
+        if distance == depth {
+            continue;
+        }
+        for &neighbor in neighbors.get(&node).into_iter().flatten() {
+            if visited.insert(neighbor) {
+                queue.push_back((neighbor, distance + 1));
+            }
+        }
+    }
+    visited
+}
+
+/// Remember the alpha of an entity before it is first highlighted. Returns
+/// the alpha to restore afterwards.
+fn original_alpha(
+    commands: &mut Commands,
+    entity: Entity,
+    alpha: f32,
+    highlight: Option<&HoverHighlight>,
+) -> f32 {
+    let original_alpha = highlight.map_or(alpha, |h| h.original_alpha);
+    commands
+        .entity(entity)
+        .insert(HoverHighlight { original_alpha });
+    original_alpha
+}
+
+/// Observer for pointer-over events on nodes. Emphasizes the node, its
+/// neighborhood and the links within it, and dims everything else.
+pub fn highlight_neighborhood(
+    trigger: Trigger<Pointer<Over>>,
+    mut nodes_q: Query<(Entity, &mut NodeColor, Option<&HoverHighlight>), With<NodePhysics>>,
+    mut links_q: Query<
+        (Entity, &NodeLink, &mut LinkColor, Option<&HoverHighlight>),
+        Without<NodePhysics>,
+    >,
+    settings: Res<HoverSettings>,
+    mut commands: Commands,
+) {
+    let neighborhood = neighborhood(
+        trigger.entity(),
+        links_q
+            .iter()
+            .map(|(_, link, _, _)| (link.source, link.target)),
+        settings.depth,
+    );
+
+    for (entity, mut color, highlight) in &mut nodes_q {
+        let alpha = original_alpha(&mut commands, entity, color.0.alpha(), highlight);
+        if neighborhood.contains(&entity) {
+            color.0.set_alpha(alpha);
+        } else {
+            color.0.set_alpha(alpha * settings.dimmed_alpha);
+        }
+    }
+    for (entity, link, mut color, highlight) in &mut links_q {
+        let alpha = original_alpha(&mut commands, entity, color.0.alpha(), highlight);
+        if neighborhood.contains(&link.source) && neighborhood.contains(&link.target) {
+            color.0.set_alpha(1.0);
+        } else {
+            color.0.set_alpha(alpha * settings.dimmed_alpha);
+        }
+    }
+}
+
+/// Observer for pointer-out events on nodes. Restores the alpha of all nodes
+/// and links.
+pub fn clear_highlight(
+    _trigger: Trigger<Pointer<Out>>,
+    mut highlighted_q: Query<(
+        Entity,
+        &HoverHighlight,
+        Option<&mut NodeColor>,
+        Option<&mut LinkColor>,
+    )>,
+    mut commands: Commands,
+) {
+    for (entity, highlight, node_color, link_color) in &mut highlighted_q {
+        if let Some(mut color) = node_color {
+            color.0.set_alpha(highlight.original_alpha);
+        }
+        if let Some(mut color) = link_color {
+            color.0.set_alpha(highlight.original_alpha);
+        }
+        commands.entity(entity).remove::<HoverHighlight>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::neighborhood;
+    use bevy::{ecs::entity::Entity, utils::HashSet};
+
+    #[test]
+    fn test_neighborhood() {
+        let [a, b, c, d] = [0, 1, 2, 3].map(Entity::from_raw);
+        let links = [(a, b), (c, b), (c, d)];
+
+        assert_eq!(neighborhood(b, links, 0), HashSet::from_iter([b]));
+        assert_eq!(neighborhood(b, links, 1), HashSet::from_iter([a, b, c]));
+        assert_eq!(neighborhood(b, links, 2), HashSet::from_iter([a, b, c, d]));
+    }
+}
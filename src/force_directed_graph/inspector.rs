@@ -0,0 +1,162 @@
+use std::fmt::Write as _;
+
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    color::{Alpha as _, Color},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{With, Without},
+        system::{Commands, Query, Res, Single},
+    },
+    hierarchy::{BuildChildren as _, ChildBuild as _},
+    picking::{focus::HoverMap, pointer::PointerId},
+    text::{TextColor, TextFont},
+    time::Time,
+    transform::components::Transform,
+    ui::{widget::Text, BackgroundColor, Display, Node, PositionType, UiRect, Val},
+    utils::default,
+};
+
+use super::{
+    common::{NodeId, NodeLabel, NodeLink, NodeMetadata, NodePhysics},
+    selection::Selected,
+};
+
+/// Marker component for the inspector panel.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct InspectorPanel;
+
+/// Marker component for the text within the inspector panel.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct InspectorText;
+
+/// Plugin for a panel in the top right corner which shows details about the
+/// hovered node or link. If nothing is hovered, the first selected node is
+/// shown. The panel is hidden if there is nothing to show.
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_inspector)
+            .add_systems(Update, update_inspector);
+    }
+}
+
+pub fn setup_inspector(mut commands: Commands) {
+    commands
+        .spawn((
+            InspectorPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.0),
+                right: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                display: Display::None,
+                ..default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.6)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                InspectorText,
+                Text::default(),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
+
+/// Human-readable name of a node: its label, its id or the entity.
+fn node_name(entity: Entity, label: Option<&NodeLabel>, id: Option<&NodeId>) -> String {
+    label
+        .map(|label| label.0.clone())
+        .or_else(|| id.map(|id| id.0.clone()))
+        .unwrap_or_else(|| entity.to_string())
+}
+
+pub fn update_inspector(
+    hover_map: Res<HoverMap>,
+    nodes_q: Query<
+        (
+            Entity,
+            &Transform,
+            &NodePhysics,
+            Option<&NodeLabel>,
+            Option<&NodeId>,
+            Option<&NodeMetadata>,
+        ),
+        Without<NodeLink>,
+    >,
+    links_q: Query<(Entity, &NodeLink), Without<NodePhysics>>,
+    selected_q: Query<Entity, With<Selected>>,
+    mut panel: Single<&mut Node, With<InspectorPanel>>,
+    mut text: Single<&mut Text, With<InspectorText>>,
+    time: Res<Time>,
+) {
+    let hovered = hover_map
+        .get(&PointerId::Mouse)
+        .into_iter()
+        .flat_map(|hovered| hovered.keys());
+    let hovered_node = hovered
+        .clone()
+        .find(|entity| nodes_q.contains(**entity))
+        .copied();
+    let hovered_link = hovered.clone().find_map(|entity| links_q.get(*entity).ok());
+
+    let mut content = String::new();
+    // Writing into a String cannot fail
+    if let Some(entity) = hovered_node.or_else(|| selected_q.iter().next()) {
+        let (entity, transform, node_physics, label, id, metadata) = nodes_q.get(entity).unwrap();
+        let position = transform.translation.truncate();
+        let velocity = (position - node_physics.previous_position) / time.delta_secs().max(1e-6);
+        let degree = links_q
+            .iter()
+            .filter(|(_, link)| link.source == entity || link.target == entity)
+            .count();
+
+        let _ = writeln!(content, "Node {}", node_name(entity, label, id));
+        if let Some(metadata) = metadata {
+            for (key, value) in &metadata.0 {
+                let _ = writeln!(content, "{}: {}", key, value);
+            }
+        }
+        let _ = writeln!(content, "degree: {}", degree);
+        let _ = writeln!(content, "position: ({:.1}, {:.1})", position.x, position.y);
+        let _ = write!(
+            content,
+            "velocity: ({:.1}, {:.1}) px/s",
+            velocity.x, velocity.y
+        );
+    } else if let Some((_, link)) = hovered_link {
+        let endpoint_name = |endpoint: Entity| {
+            nodes_q.get(endpoint).map_or(endpoint.to_string(), |node| {
+                node_name(endpoint, node.3, node.4)
+            })
+        };
+
+        let _ = writeln!(
+            content,
+            "Link {} -> {}",
+            endpoint_name(link.source),
+            endpoint_name(link.target)
+        );
+        let _ = write!(content, "target distance: {:.1}", link.target_distance);
+    }
+
+    let display = if content.is_empty() {
+        Display::None
+    } else {
+        Display::Flex
+    };
+    // Avoid triggering a new layout every frame
+    if panel.display != display {
+        panel.display = display;
+    }
+    if text.0 != content {
+        text.0 = content;
+    }
+}
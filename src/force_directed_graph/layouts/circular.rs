@@ -0,0 +1,186 @@
+//! Circular layout: all nodes are placed on a single circle. Nodes of the same
+//! connected component are placed next to each other (biggest component
+//! first), and within a component nodes of the same community are grouped.
+//! Communities are detected via label propagation.
+
+use core::f32::consts::TAU;
+use std::collections::{HashSet, VecDeque};
+
+use bevy::math::Vec2;
+
+use super::LayoutGraph;
+
+/// Maximal number of label propagation rounds.
+const LABEL_PROPAGATION_ROUNDS: usize = 16;
+
+/// Compute a circular layout. Returns one position per node of `graph`.
+/// * `node_spacing` - distance between two adjacent nodes on the circle in px.
+///   Between two components, one additional gap is left.
+pub fn circular_layout(graph: &LayoutGraph, node_spacing: f32) -> Vec<Vec2> {
+    let neighbors = graph.neighbors();
+    let communities = communities(&neighbors);
+
+    let mut components = connected_components(&neighbors);
+    components.sort_by_key(|component| core::cmp::Reverse(component.len()));
+
+    // Slots on the circle, `None` is a gap between two components
+    let mut slots = Vec::with_capacity(graph.nodes.len() + components.len());
+    for mut component in components {
+        // Stable sort, so nodes within a community keep their breadth-first order
+        component.sort_by_key(|&node| communities[node]);
+        slots.extend(component.into_iter().map(Some));
+        slots.push(None);
+    }
+    // A single component does not need a gap
+    if slots.iter().filter(|slot| slot.is_none()).count() == 1 {
+        slots.pop();
+    }
+
+    let radius = slots.len() as f32 * node_spacing / TAU;
+    let mut positions = vec![Vec2::ZERO; graph.nodes.len()];
+    for (i, slot) in slots.iter().enumerate() {
+        if let Some(node) = slot {
+            positions[*node] = Vec2::from_angle(TAU * i as f32 / slots.len() as f32) * radius;
+        }
+    }
+    positions
+}
+
+/// Connected components, each in breadth-first order.
+pub fn connected_components(neighbors: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut visited = vec![false; neighbors.len()];
+    let mut components = Vec::new();
+    for root in 0..neighbors.len() {
+        if visited[root] {
+            continue;
+        }
+
+        let mut component = Vec::new();
+        visited[root] = true;
+        let mut queue = VecDeque::from([root]);
+        while let Some(node) = queue.pop_front() {
+            component.push(node);
+            for &neighbor in &neighbors[node] {
+                if !visited[neighbor] {
+                    visited[neighbor] = true;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        components.push(component);
+    }
+    components
+}
+
+/// Community label per node, determined via label propagation: every node
+/// starts with its own label and repeatedly adopts the label with the highest
+/// weight among its neighbors (ties are broken by the smallest label, so the
+/// result is deterministic).
+///
+/// A neighbor weighs one plus the number of neighbors both nodes have in
+/// common. Thus, links within densely connected groups outweigh links bridging
+/// two groups.
+pub fn communities(neighbors: &[Vec<usize>]) -> Vec<usize> {
+    let neighbor_sets = neighbors
+        .iter()
+        .map(|neighbors| neighbors.iter().copied().collect::<HashSet<_>>())
+        .collect::<Vec<_>>();
+    let weights = neighbors
+        .iter()
+        .enumerate()
+        .map(|(node, neighbors)| {
+            neighbors
+                .iter()
+                .map(|&neighbor| {
+                    1 + neighbor_sets[node]
+                        .intersection(&neighbor_sets[neighbor])
+                        .count()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut labels = (0..neighbors.len()).collect::<Vec<_>>();
+    for _ in 0..LABEL_PROPAGATION_ROUNDS {
+        let mut changed = false;
+        for node in 0..neighbors.len() {
+            let mut neighbor_labels = neighbors[node]
+                .iter()
+                .zip(&weights[node])
+                .map(|(&neighbor, &weight)| (labels[neighbor], weight))
+                .collect::<Vec<_>>();
+            if neighbor_labels.is_empty() {
+                continue;
+            }
+            neighbor_labels.sort_unstable();
+
+            // Label with the highest weight, the smallest one if there are several
+            let mut best = (0, labels[node]);
+            for chunk in neighbor_labels.chunk_by(|(a, _), (b, _)| a == b) {
+                let weight = chunk.iter().map(|(_, weight)| weight).sum::<usize>();
+                if weight > best.0 {
+                    best = (weight, chunk[0].0);
+                }
+            }
+
+            if best.1 != labels[node] {
+                labels[node] = best.1;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    labels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::entity::Entity;
+
+    #[test]
+    fn test_components_are_adjacent() {
+        // Two triangles: 0, 2, 4 and 1, 3, 5
+        let graph = LayoutGraph {
+            nodes: (0..6).map(Entity::from_raw).collect(),
+            edges: vec![(0, 2), (2, 4), (4, 0), (1, 3), (3, 5), (5, 1)],
+        };
+
+        let positions = circular_layout(&graph, 10.0);
+
+        // 6 nodes and 2 gaps
+        let radius = 8.0 * 10.0 / TAU;
+        assert!(positions.iter().all(|p| (p.length() - radius).abs() < 1e-3));
+        let angle_of = |node: usize| Vec2::X.angle_to(positions[node]).rem_euclid(TAU);
+        let step = TAU / 8.0;
+        let mut first = [0, 2, 4].map(|n| (angle_of(n) / step).round() as usize);
+        first.sort_unstable();
+        assert_eq!(first, [0, 1, 2]);
+        let mut second = [1, 3, 5].map(|n| (angle_of(n) / step).round() as usize);
+        second.sort_unstable();
+        assert_eq!(second, [4, 5, 6]);
+    }
+
+    #[test]
+    fn test_communities() {
+        // Two triangles connected by the link 2 - 3
+        let neighbors = [
+            vec![1, 2],
+            vec![0, 2],
+            vec![0, 1, 3],
+            vec![2, 4, 5],
+            vec![3, 5],
+            vec![3, 4],
+        ];
+
+        let labels = communities(&neighbors);
+
+        assert_eq!(labels[0], labels[1]);
+        assert_eq!(labels[0], labels[2]);
+        assert_eq!(labels[3], labels[4]);
+        assert_eq!(labels[3], labels[5]);
+        assert_ne!(labels[0], labels[3]);
+    }
+}
@@ -0,0 +1,305 @@
+//! Layered (Sugiyama-style) layout for directed graphs. Directed flows read
+//! top-to-bottom, i.e. link sources are placed above link targets.
+//!
+//! The layout is computed in four phases:
+//! 1. cycle removal: edges closing a cycle are reversed
+//! 2. layer assignment: longest path from the sources, long edges are split
+//!    into chains of dummy nodes
+//! 3. crossing minimization: barycenter heuristic, sweeping down and up
+//! 4. coordinate assignment: layers are centered horizontally and spaced
+//!    evenly vertically
+
+use bevy::math::Vec2;
+
+use super::LayoutGraph;
+
+/// Number of down-and-up sweeps of the barycenter heuristic.
+const CROSSING_MINIMIZATION_SWEEPS: usize = 8;
+
+/// Compute a layered layout. Returns one position per node of `graph`.
+pub fn hierarchical_layout(
+    graph: &LayoutGraph,
+    layer_spacing: f32,
+    node_spacing: f32,
+) -> Vec<Vec2> {
+    let edges = remove_cycles(graph);
+    let node_layers = assign_layers(graph.nodes.len(), &edges);
+    let (layers, edges) = split_long_edges(&node_layers, &edges);
+    let layers = minimize_crossings(layers, &edges);
+
+    let mut positions = vec![Vec2::ZERO; graph.nodes.len()];
+    let layer_offset = (layers.len() as f32 - 1.0) / 2.0;
+    for (layer_index, layer) in layers.iter().enumerate() {
+        let node_offset = (layer.len() as f32 - 1.0) / 2.0;
+        for (position_in_layer, &node) in layer.iter().enumerate() {
+            // Dummy nodes are only needed for crossing minimization
+            if node < positions.len() {
+                positions[node] = Vec2::new(
+                    (position_in_layer as f32 - node_offset) * node_spacing,
+                    (layer_offset - layer_index as f32) * layer_spacing,
+                );
+            }
+        }
+    }
+    positions
+}
+
+/// Return the edges of the graph without self-loops and duplicates, where all
+/// edges closing a cycle (back edges of a depth-first search) are reversed.
+fn remove_cycles(graph: &LayoutGraph) -> Vec<(usize, usize)> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Unvisited,
+        OnStack,
+        Done,
+    }
+
+    let successors = graph.successors();
+    let mut states = vec![State::Unvisited; graph.nodes.len()];
+    let mut edges = Vec::new();
+
+    for root in 0..graph.nodes.len() {
+        if states[root] != State::Unvisited {
+            continue;
+        }
+
+        // Iterative depth-first search, the stack holds (node, next successor index)
+        states[root] = State::OnStack;
+        let mut stack = vec![(root, 0)];
+        while let Some((node, next)) = stack.last_mut() {
+            let node = *node;
+            let Some(&successor) = successors[node].get(*next) else {
+                states[node] = State::Done;
+                stack.pop();
+                continue;
+            };
+            *next += 1;
+
+            match states[successor] {
+                State::Unvisited => {
+                    edges.push((node, successor));
+                    states[successor] = State::OnStack;
+                    stack.push((successor, 0));
+                }
+                State::OnStack => edges.push((successor, node)),
+                State::Done => edges.push((node, successor)),
+            }
+        }
+    }
+
+    edges.sort_unstable();
+    edges.dedup();
+    edges
+}
+
+/// Assign every node to the layer of the longest path from any source to the
+/// node. `edges` must be acyclic.
+fn assign_layers(node_count: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut successors = vec![Vec::new(); node_count];
+    let mut in_degrees = vec![0; node_count];
+    for &(source, target) in edges {
+        successors[source].push(target);
+        in_degrees[target] += 1;
+    }
+
+    // Kahn's algorithm visits nodes in topological order
+    let mut layers = vec![0; node_count];
+    let mut queue = (0..node_count)
+        .filter(|&node| in_degrees[node] == 0)
+        .collect::<Vec<_>>();
+    while let Some(node) = queue.pop() {
+        for &successor in &successors[node] {
+            layers[successor] = layers[successor].max(layers[node] + 1);
+            in_degrees[successor] -= 1;
+            if in_degrees[successor] == 0 {
+                queue.push(successor);
+            }
+        }
+    }
+    layers
+}
+
+/// Group nodes into layers. Edges spanning more than one layer are replaced by
+/// chains of dummy nodes (indices `node_layers.len()..`), so that afterwards
+/// all edges connect adjacent layers.
+fn split_long_edges(
+    node_layers: &[usize],
+    edges: &[(usize, usize)],
+) -> (Vec<Vec<usize>>, Vec<(usize, usize)>) {
+    let layer_count = node_layers.iter().max().map_or(0, |max| max + 1);
+    let mut layers = vec![Vec::new(); layer_count];
+    for (node, &layer) in node_layers.iter().enumerate() {
+        layers[layer].push(node);
+    }
+
+    let mut next_dummy = node_layers.len();
+    let mut short_edges = Vec::new();
+    for &(source, target) in edges {
+        let mut previous = source;
+        let spanned_layers = (node_layers[source] + 1)..node_layers[target];
+        for layer in &mut layers[spanned_layers] {
+            layer.push(next_dummy);
+            short_edges.push((previous, next_dummy));
+            previous = next_dummy;
+            next_dummy += 1;
+        }
+        short_edges.push((previous, target));
+    }
+
+    (layers, short_edges)
+}
+
+/// Reorder the nodes within their layers to reduce the number of crossings,
+/// using the barycenter heuristic. `edges` must connect adjacent layers only.
+fn minimize_crossings(mut layers: Vec<Vec<usize>>, edges: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let node_count = layers.iter().map(Vec::len).sum::<usize>();
+    let mut predecessors = vec![Vec::new(); node_count];
+    let mut successors = vec![Vec::new(); node_count];
+    for &(source, target) in edges {
+        successors[source].push(target);
+        predecessors[target].push(source);
+    }
+
+    let mut best_layers = layers.clone();
+    let mut best_crossings = count_crossings(&layers, &successors);
+    for _ in 0..CROSSING_MINIMIZATION_SWEEPS {
+        if best_crossings == 0 {
+            break;
+        }
+
+        for layer in 1..layers.len() {
+            order_by_barycenter(&mut layers, layer, layer - 1, &predecessors);
+        }
+        for layer in (0..layers.len().saturating_sub(1)).rev() {
+            order_by_barycenter(&mut layers, layer, layer + 1, &successors);
+        }
+
+        let crossings = count_crossings(&layers, &successors);
+        if crossings < best_crossings {
+            best_crossings = crossings;
+            best_layers = layers.clone();
+        }
+    }
+    best_layers
+}
+
+/// Sort `layer` by the mean position of each node's neighbors in the fixed
+/// layer. Nodes without neighbors keep their position.
+fn order_by_barycenter(
+    layers: &mut [Vec<usize>],
+    layer: usize,
+    fixed_layer: usize,
+    neighbors: &[Vec<usize>],
+) {
+    let mut positions = vec![0.0; neighbors.len()];
+    for (position, &node) in layers[fixed_layer].iter().enumerate() {
+        positions[node] = position as f32;
+    }
+
+    let mut barycenters = layers[layer]
+        .iter()
+        .enumerate()
+        .map(|(position, &node)| {
+            let barycenter = if neighbors[node].is_empty() {
+                position as f32
+            } else {
+                neighbors[node].iter().map(|&n| positions[n]).sum::<f32>()
+                    / neighbors[node].len() as f32
+            };
+            (barycenter, node)
+        })
+        .collect::<Vec<_>>();
+    // Stable sort, so ties keep their current order
+    barycenters.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    layers[layer] = barycenters.into_iter().map(|(_, node)| node).collect();
+}
+
+/// Number of pairwise crossings of edges between adjacent layers.
+fn count_crossings(layers: &[Vec<usize>], successors: &[Vec<usize>]) -> usize {
+    let mut positions = vec![0; successors.len()];
+    for layer in layers {
+        for (position, &node) in layer.iter().enumerate() {
+            positions[node] = position;
+        }
+    }
+
+    layers
+        .iter()
+        .map(|layer| {
+            let edges = layer
+                .iter()
+                .flat_map(|&source| {
+                    successors[source]
+                        .iter()
+                        .map(|&target| (positions[source], positions[target]))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            edges
+                .iter()
+                .enumerate()
+                .map(|(i, a)| {
+                    edges[i + 1..]
+                        .iter()
+                        .filter(|b| (a.0 < b.0 && a.1 > b.1) || (a.0 > b.0 && a.1 < b.1))
+                        .count()
+                })
+                .sum::<usize>()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::entity::Entity;
+
+    fn graph(node_count: u32, edges: &[(usize, usize)]) -> LayoutGraph {
+        LayoutGraph {
+            nodes: (0..node_count).map(Entity::from_raw).collect(),
+            edges: edges.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_chain_reads_top_to_bottom() {
+        let positions = hierarchical_layout(&graph(3, &[(0, 1), (1, 2)]), 100.0, 50.0);
+
+        assert_eq!(
+            positions,
+            [
+                Vec2::new(0.0, 100.0),
+                Vec2::new(0.0, 0.0),
+                Vec2::new(0.0, -100.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cycle_is_layered() {
+        let positions = hierarchical_layout(&graph(3, &[(0, 1), (1, 2), (2, 0)]), 100.0, 50.0);
+
+        let mut ys = positions.iter().map(|p| p.y).collect::<Vec<_>>();
+        ys.sort_by(f32::total_cmp);
+        assert_eq!(ys, [-100.0, 0.0, 100.0]);
+    }
+
+    #[test]
+    fn test_crossings_are_removed() {
+        // 0 and 1 on top, 2 and 3 below; edges cross if 2 and 3 keep their order
+        let positions = hierarchical_layout(&graph(4, &[(0, 3), (1, 2)]), 100.0, 50.0);
+
+        assert!(positions[0].x < positions[1].x);
+        assert!(positions[3].x < positions[2].x);
+    }
+
+    #[test]
+    fn test_long_edges_get_dummy_nodes() {
+        let node_layers = [0, 1, 2];
+
+        let (layers, edges) = split_long_edges(&node_layers, &[(0, 1), (1, 2), (0, 2)]);
+
+        assert_eq!(layers, [vec![0], vec![1, 3], vec![2]]);
+        assert_eq!(edges, [(0, 1), (1, 2), (0, 3), (3, 2)]);
+    }
+}
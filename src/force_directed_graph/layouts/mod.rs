@@ -0,0 +1,319 @@
+use bevy::{
+    app::{App, Plugin, PreUpdate, Update},
+    ecs::{
+        entity::Entity,
+        event::{Event, EventReader, EventWriter},
+        query::{With, Without},
+        schedule::IntoSystemSetConfigs as _,
+        system::{Commands, Local, Query, Res, ResMut},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    transform::components::Transform,
+    utils::HashMap,
+};
+
+use super::{
+    common::{NodeLink, NodePhysics},
+    forces::layer_target::LayerTarget,
+    tween::{self, TweenSettings},
+    ForceModel, SimulationSet,
+};
+
+pub mod circular;
+pub mod hierarchical;
+pub mod radial;
+
+/// Nodes and links of the graph, independent of the ECS. Layout algorithms
+/// refer to nodes by their index in `nodes`.
+#[derive(Debug, Default, Clone)]
+pub struct LayoutGraph {
+    pub nodes: Vec<Entity>,
+    /// Directed edges (source index, target index)
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl LayoutGraph {
+    /// Collect the graph from node entities and their links. Links between
+    /// entities which are not part of `nodes` are ignored.
+    pub fn new(
+        nodes: impl IntoIterator<Item = Entity>,
+        links: impl IntoIterator<Item = (Entity, Entity)>,
+    ) -> Self {
+        let nodes = nodes.into_iter().collect::<Vec<_>>();
+        let indices = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, entity)| (*entity, i))
+            .collect::<HashMap<_, _>>();
+        let edges = links
+            .into_iter()
+            .filter_map(|(source, target)| Some((*indices.get(&source)?, *indices.get(&target)?)))
+            .collect();
+
+        Self { nodes, edges }
+    }
+
+    /// Outgoing neighbors per node, ignoring self-loops.
+    pub fn successors(&self) -> Vec<Vec<usize>> {
+        let mut successors = vec![Vec::new(); self.nodes.len()];
+        for &(source, target) in &self.edges {
+            if source != target {
+                successors[source].push(target);
+            }
+        }
+        successors
+    }
+
+    /// Neighbors per node in both directions, ignoring self-loops.
+    pub fn neighbors(&self) -> Vec<Vec<usize>> {
+        let mut neighbors = vec![Vec::new(); self.nodes.len()];
+        for &(source, target) in &self.edges {
+            if source != target {
+                neighbors[source].push(target);
+                neighbors[target].push(source);
+            }
+        }
+        neighbors
+    }
+}
+
+/// Event to replace the current node positions by a layout.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum ApplyLayout {
+    /// Remove all layout targets and let the forces take over again.
+    Force,
+    /// Layered layout for directed acyclic graphs, see [hierarchical].
+    Hierarchical {
+        /// If true, nodes keep being pulled to the y coordinate of their layer
+        /// (see [LayerTarget]) while the forces are still applied. Otherwise,
+        /// the layout is static.
+        layer_targets: bool,
+    },
+    /// Radial tree layout around the given root, see [radial]. If no root is
+    /// given, the node with the most links is used.
+    RadialTree { root: Option<Entity> },
+    /// All nodes on a circle, grouped by component and community, see
+    /// [circular].
+    Circular,
+}
+
+impl ApplyLayout {
+    /// Whether the nodes stay at the positions of the layout, i.e. the
+    /// simulation is suspended.
+    pub fn is_static(&self) -> bool {
+        !matches!(
+            self,
+            Self::Force
+                | Self::Hierarchical {
+                    layer_targets: true
+                }
+        )
+    }
+}
+
+/// Plugin for switching between layouts. Layouts are applied on an
+/// [ApplyLayout] event, some of them can be triggered via keyboard:
+///
+/// * `F` - [ApplyLayout::Force]
+/// * `H` - [ApplyLayout::Hierarchical] with layer targets
+/// * `R` - [ApplyLayout::RadialTree] around the node with the most links
+/// * `C` - [ApplyLayout::Circular]
+///
+/// Static layouts (all but [ApplyLayout::Force] and the hierarchical layout
+/// with layer targets) switch to [ForceModel::Static], which suspends the
+/// simulation until the force layout is requested again.
+pub struct LayoutPlugin {
+    /// Distance between two layers (hierarchical layout) in px
+    pub layer_spacing: f32,
+    /// Distance between two rings (radial tree layout) in px
+    pub ring_spacing: f32,
+    /// Distance between two nodes within a layer (hierarchical layout) or on
+    /// the circle (circular layout) in px
+    pub node_spacing: f32,
+}
+
+impl Default for LayoutPlugin {
+    fn default() -> Self {
+        Self {
+            layer_spacing: 100.0,
+            ring_spacing: 100.0,
+            node_spacing: 60.0,
+        }
+    }
+}
+
+impl Plugin for LayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ForceModel>()
+            .add_event::<ApplyLayout>()
+            .configure_sets(
+                PreUpdate,
+                SimulationSet::Integration.run_if(simulation_dynamic),
+            )
+            .configure_sets(
+                Update,
+                (SimulationSet::Forces, SimulationSet::Constraints).run_if(simulation_dynamic),
+            )
+            .add_systems(
+                Update,
+                (
+                    layout_keyboard_input,
+                    apply_layout(self.layer_spacing, self.ring_spacing, self.node_spacing),
+                ),
+            );
+    }
+}
+
+/// Run condition: false while a static layout is shown, see
+/// [ForceModel::Static].
+pub fn simulation_dynamic(force_model: Res<ForceModel>) -> bool {
+    *force_model != ForceModel::Static
+}
+
+/// Send [ApplyLayout] events on key presses. See [LayoutPlugin].
+pub fn layout_keyboard_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut layout_events: EventWriter<ApplyLayout>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyF) {
+        layout_events.send(ApplyLayout::Force);
+    }
+    if keyboard.just_pressed(KeyCode::KeyH) {
+        layout_events.send(ApplyLayout::Hierarchical {
+            layer_targets: true,
+        });
+    }
+    if keyboard.just_pressed(KeyCode::KeyR) {
+        layout_events.send(ApplyLayout::RadialTree { root: None });
+    }
+    if keyboard.just_pressed(KeyCode::KeyC) {
+        layout_events.send(ApplyLayout::Circular);
+    }
+}
+
+/// Compute the requested layout and move the nodes accordingly. Static
+/// layouts suspend the simulation (see [ForceModel::Static]), the other ones
+/// restore the force model which was selected before.
+pub fn apply_layout(
+    layer_spacing: f32,
+    ring_spacing: f32,
+    node_spacing: f32,
+) -> impl Fn(
+    EventReader<'_, '_, ApplyLayout>,
+    Query<'_, '_, (Entity, &mut Transform, &mut NodePhysics)>,
+    Query<'_, '_, &NodeLink, Without<NodePhysics>>,
+    Query<'_, '_, Entity, With<LayerTarget>>,
+    Option<Res<'_, TweenSettings>>,
+    ResMut<'_, ForceModel>,
+    Local<'_, ForceModel>,
+    Commands<'_, '_>,
+) {
+    move |mut layout_events: EventReader<ApplyLayout>,
+          mut nodes_q: Query<(Entity, &mut Transform, &mut NodePhysics)>,
+          links_q: Query<&NodeLink, Without<NodePhysics>>,
+          layer_targets_q: Query<Entity, With<LayerTarget>>,
+          tween_settings: Option<Res<TweenSettings>>,
+          mut force_model: ResMut<ForceModel>,
+          mut dynamic_force_model: Local<ForceModel>,
+          mut commands: Commands| {
+        for layout in layout_events.read() {
+            // Any previous layout targets are replaced
+            for entity in &layer_targets_q {
+                commands.entity(entity).remove::<LayerTarget>();
+            }
+
+            if layout.is_static() {
+                if *force_model != ForceModel::Static {
+                    *dynamic_force_model = *force_model;
+                    *force_model = ForceModel::Static;
+                }
+            } else if *force_model == ForceModel::Static {
+                *force_model = *dynamic_force_model;
+            }
+
+            let graph = LayoutGraph::new(
+                nodes_q.iter().map(|(entity, _, _)| entity),
+                links_q.iter().map(|link| (link.source, link.target)),
+            );
+
+            let positions = match *layout {
+                ApplyLayout::Force => continue,
+                ApplyLayout::Hierarchical { layer_targets } => {
+                    let positions =
+                        hierarchical::hierarchical_layout(&graph, layer_spacing, node_spacing);
+                    if layer_targets {
+                        for (entity, position) in graph.nodes.iter().zip(&positions) {
+                            commands
+                                .entity(*entity)
+                                .insert(LayerTarget { y: position.y });
+                        }
+                    }
+                    positions
+                }
+                ApplyLayout::RadialTree { root } => {
+                    let root = root
+                        .and_then(|root| graph.nodes.iter().position(|node| *node == root))
+                        .or_else(|| radial::max_degree_node(&graph));
+                    let Some(root) = root else {
+                        continue;
+                    };
+                    radial::radial_tree_layout(&graph, root, ring_spacing)
+                }
+                ApplyLayout::Circular => circular::circular_layout(&graph, node_spacing),
+            };
+
+            for (entity, position) in graph.nodes.iter().zip(positions) {
+                if let Ok((_, mut transform, mut node_physics)) = nodes_q.get_mut(*entity) {
+                    tween::move_node(
+                        &mut commands,
+                        *entity,
+                        &mut transform,
+                        &mut node_physics,
+                        position,
+                        tween_settings.as_deref(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ApplyLayout, LayoutPlugin};
+    use crate::force_directed_graph::{common::NodePhysics, ForceModel};
+    use bevy::{
+        app::App,
+        input::{keyboard::KeyCode, ButtonInput},
+        transform::components::Transform,
+    };
+
+    #[test]
+    fn test_static_layout() {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .insert_resource(ForceModel::FruchtermanReingold)
+            .add_plugins(LayoutPlugin::default());
+        for x in [0.0, 10.0, 20.0] {
+            let transform = Transform::from_xyz(x, 0.0, 0.0);
+            app.world_mut()
+                .spawn((transform, NodePhysics::from_transform(transform)));
+        }
+
+        app.world_mut().send_event(ApplyLayout::Circular);
+        app.update();
+        assert_eq!(*app.world().resource::<ForceModel>(), ForceModel::Static);
+
+        app.world_mut()
+            .send_event(ApplyLayout::RadialTree { root: None });
+        app.update();
+        assert_eq!(*app.world().resource::<ForceModel>(), ForceModel::Static);
+
+        app.world_mut().send_event(ApplyLayout::Force);
+        app.update();
+        assert_eq!(
+            *app.world().resource::<ForceModel>(),
+            ForceModel::FruchtermanReingold
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! Radial tree layout: the root is placed in the center and all other nodes
+//! on rings around it, where the ring is the (undirected) hop distance to the
+//! root. Every subtree of the breadth-first search tree gets an angular wedge
+//! proportional to its number of leaves, so subtrees do not overlap.
+
+use core::f32::consts::TAU;
+use std::collections::VecDeque;
+
+use bevy::math::Vec2;
+
+use super::LayoutGraph;
+
+/// Compute a radial tree layout around the node with index `root`. Returns one
+/// position per node of `graph`. Nodes which cannot be reached from the root
+/// are distributed on an additional outermost ring.
+pub fn radial_tree_layout(graph: &LayoutGraph, root: usize, ring_spacing: f32) -> Vec<Vec2> {
+    let neighbors = graph.neighbors();
+    let node_count = graph.nodes.len();
+
+    // Breadth-first search tree
+    let mut depths = vec![None; node_count];
+    let mut children = vec![Vec::new(); node_count];
+    let mut order = Vec::with_capacity(node_count);
+    depths[root] = Some(0);
+    let mut queue = VecDeque::from([root]);
+    while let Some(node) = queue.pop_front() {
+        order.push(node);
+        for &neighbor in &neighbors[node] {
+            if depths[neighbor].is_none() {
+                depths[neighbor] = Some(depths[node].unwrap_or(0) + 1);
+                children[node].push(neighbor);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    // Number of leaves per subtree, children are visited before their parents
+    let mut leaves = vec![1usize; node_count];
+    for &node in order.iter().rev() {
+        if !children[node].is_empty() {
+            leaves[node] = children[node].iter().map(|&child| leaves[child]).sum();
+        }
+    }
+
+    // Assign wedges top-down, every node is placed in the middle of its wedge
+    let mut positions = vec![Vec2::ZERO; node_count];
+    let mut wedges = vec![(0.0, TAU); node_count];
+    for &node in &order {
+        let (start, end) = wedges[node];
+        let radius = depths[node].unwrap_or(0) as f32 * ring_spacing;
+        positions[node] = Vec2::from_angle((start + end) / 2.0) * radius;
+
+        let mut child_start = start;
+        for &child in &children[node] {
+            let child_end =
+                child_start + (end - start) * leaves[child] as f32 / leaves[node] as f32;
+            wedges[child] = (child_start, child_end);
+            child_start = child_end;
+        }
+    }
+
+    // Unreachable nodes
+    let max_depth = depths.iter().flatten().max().copied().unwrap_or(0);
+    let unreachable = (0..node_count)
+        .filter(|&node| depths[node].is_none())
+        .collect::<Vec<_>>();
+    for (i, &node) in unreachable.iter().enumerate() {
+        let angle = TAU * i as f32 / unreachable.len() as f32;
+        positions[node] = Vec2::from_angle(angle) * (max_depth + 1) as f32 * ring_spacing;
+    }
+
+    positions
+}
+
+/// Index of the node with the most links, a sensible default root.
+pub fn max_degree_node(graph: &LayoutGraph) -> Option<usize> {
+    graph
+        .neighbors()
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, neighbors)| neighbors.len())
+        .map(|(node, _)| node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::entity::Entity;
+
+    fn graph(node_count: u32, edges: &[(usize, usize)]) -> LayoutGraph {
+        LayoutGraph {
+            nodes: (0..node_count).map(Entity::from_raw).collect(),
+            edges: edges.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_nodes_are_placed_on_rings() {
+        // 0 - 1 - 2, 0 - 3, 4 is unreachable
+        let positions = radial_tree_layout(&graph(5, &[(0, 1), (2, 1), (0, 3)]), 0, 100.0);
+
+        let radii = positions.iter().map(|p| p.length()).collect::<Vec<_>>();
+        assert_eq!(radii[0], 0.0);
+        assert!((radii[1] - 100.0).abs() < 1e-3);
+        assert!((radii[2] - 200.0).abs() < 1e-3);
+        assert!((radii[3] - 100.0).abs() < 1e-3);
+        assert!((radii[4] - 300.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_max_degree_node() {
+        assert_eq!(
+            max_degree_node(&graph(4, &[(0, 1), (2, 1), (1, 3)])),
+            Some(1)
+        );
+        assert_eq!(max_degree_node(&graph(0, &[])), None);
+    }
+}
@@ -0,0 +1,241 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    color::{Alpha as _, Color, Mix as _, Oklaba},
+    ecs::{
+        change_detection::{DetectChanges as _, DetectChangesMut as _},
+        entity::Entity,
+        query::Changed,
+        removal_detection::RemovedComponents,
+        schedule::IntoSystemConfigs as _,
+        system::{Commands, Query, Res, Resource},
+    },
+};
+
+use super::{
+    common::{LinkColor, LinkStyle, LinkWeight},
+    hover::HoverHighlight,
+};
+
+/// How a weight is mapped onto the range `[0, 1]`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Scale {
+    #[default]
+    Linear,
+    /// Logarithmic, for weights spanning several orders of magnitude. Weights
+    /// must be positive.
+    Log,
+}
+
+impl Scale {
+    /// Map `value` within `[min, max]` onto `[0, 1]`. Values outside are
+    /// clamped. If the domain is empty, 0.5 is returned.
+    pub fn normalize(&self, value: f32, min: f32, max: f32) -> f32 {
+        let (value, min, max) = match self {
+            Scale::Linear => (value, min, max),
+            Scale::Log => (
+                value.max(f32::EPSILON).ln(),
+                min.max(f32::EPSILON).ln(),
+                max.max(f32::EPSILON).ln(),
+            ),
+        };
+        if max - min <= f32::EPSILON {
+            0.5
+        } else {
+            ((value - min) / (max - min)).clamp(0.0, 1.0)
+        }
+    }
+}
+
+/// Colors evenly spread over `[0, 1]`, interpolated in the Oklab color space.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorRamp(pub Vec<Color>);
+
+impl ColorRamp {
+    pub fn sample(&self, t: f32) -> Color {
+        match self.0.as_slice() {
+            [] => Color::WHITE,
+            [color] => *color,
+            colors => {
+                let position = t.clamp(0.0, 1.0) * (colors.len() - 1) as f32;
+                let i = (position.floor() as usize).min(colors.len() - 2);
+                let a = Oklaba::from(colors[i]);
+                let b = Oklaba::from(colors[i + 1]);
+                a.mix(&b, position - i as f32).into()
+            }
+        }
+    }
+}
+
+/// Scales which map a [LinkWeight] to a [LinkStyle].
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct LinkStyleScales {
+    pub scale: Scale,
+    /// Weights mapped to the lowest/highest thickness and the ends of the
+    /// color ramp. If `None`, the range of all weights is used.
+    pub domain: Option<(f32, f32)>,
+    /// Lowest and highest thickness in px
+    pub thickness: (f32, f32),
+    pub color_ramp: ColorRamp,
+}
+
+impl LinkStyleScales {
+    pub fn style(&self, weight: f32, domain: (f32, f32)) -> LinkStyle {
+        let (min, max) = self.domain.unwrap_or(domain);
+        let t = self.scale.normalize(weight, min, max);
+        LinkStyle {
+            thickness: self.thickness.0 + t * (self.thickness.1 - self.thickness.0),
+            color: self.color_ramp.sample(t),
+        }
+    }
+}
+
+/// Plugin for drawing links by their [LinkWeight]: the heavier a link, the
+/// thicker it is and the further along the color ramp. Links without a
+/// weight keep their [LinkStyle].
+pub struct LinkStylePlugin {
+    pub scales: LinkStyleScales,
+}
+
+impl Default for LinkStylePlugin {
+    fn default() -> Self {
+        Self {
+            scales: LinkStyleScales {
+                scale: Scale::Linear,
+                domain: None,
+                thickness: (1.0, 6.0),
+                color_ramp: ColorRamp(vec![
+                    Color::srgba(1.0, 1.0, 1.0, 0.2),
+                    Color::srgba(1.0, 1.0, 1.0, 0.9),
+                ]),
+            },
+        }
+    }
+}
+
+impl Plugin for LinkStylePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.scales.clone()).add_systems(
+            Update,
+            (style_links_by_weight, color_links_by_style).chain(),
+        );
+    }
+}
+
+/// Derive the [LinkStyle] of all weighted links whenever a weight or the
+/// scales change, or weighted links are removed (which may change the range of
+/// all weights). Only links whose style actually changed are updated.
+pub fn style_links_by_weight(
+    links_q: Query<(Entity, &LinkWeight, Option<&LinkStyle>)>,
+    changed_q: Query<(), Changed<LinkWeight>>,
+    mut removed_weights: RemovedComponents<LinkWeight>,
+    scales: Res<LinkStyleScales>,
+    mut commands: Commands,
+) {
+    let removed = removed_weights.read().count() > 0;
+    if changed_q.is_empty() && !removed && !scales.is_changed() {
+        return;
+    }
+
+    let domain = links_q
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(min, max), (_, weight, _)| {
+            (min.min(weight.0), max.max(weight.0))
+        });
+    for (entity, weight, style) in &links_q {
+        let new_style = scales.style(weight.0, domain);
+        if style != Some(&new_style) {
+            commands.entity(entity).insert(new_style);
+        }
+    }
+}
+
+/// Apply the color of changed link styles to the links' [LinkColor]. Links
+/// dimmed by the hover highlight keep their current alpha, the style's alpha
+/// is restored once the highlight is cleared.
+pub fn color_links_by_style(
+    mut links_q: Query<
+        (&LinkStyle, &mut LinkColor, Option<&mut HoverHighlight>),
+        Changed<LinkStyle>,
+    >,
+) {
+    for (style, mut color, highlight) in &mut links_q {
+        match highlight {
+            Some(mut highlight) => {
+                color.set_if_neq(LinkColor(style.color.with_alpha(color.0.alpha())));
+                highlight.original_alpha = style.color.alpha();
+            }
+            None => {
+                color.set_if_neq(LinkColor(style.color));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{style_links_by_weight, ColorRamp, LinkStyleScales, Scale};
+    use crate::force_directed_graph::common::{LinkStyle, LinkWeight};
+    use bevy::{
+        app::{App, Update},
+        color::{Color, Srgba},
+        ecs::{change_detection::DetectChanges as _, entity::Entity},
+    };
+
+    #[test]
+    fn test_scale() {
+        assert_eq!(Scale::Linear.normalize(5.0, 0.0, 10.0), 0.5);
+        assert_eq!(Scale::Linear.normalize(20.0, 0.0, 10.0), 1.0);
+        assert_eq!(Scale::Linear.normalize(3.0, 3.0, 3.0), 0.5);
+        assert!((Scale::Log.normalize(10.0, 1.0, 100.0) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_color_ramp() {
+        let ramp = ColorRamp(vec![Color::BLACK, Color::WHITE, Color::BLACK]);
+        // Compare as 8-bit colors, as the conversion to Oklab is lossy
+        let hex = |t: f32| Srgba::from(ramp.sample(t)).to_hex();
+        assert_eq!(hex(0.0), "#000000");
+        assert_eq!(hex(0.5), "#FFFFFF");
+        assert_eq!(hex(1.0), "#000000");
+        assert_eq!(hex(2.0), "#000000");
+        assert_eq!(ColorRamp(vec![]).sample(0.5), Color::WHITE);
+    }
+
+    #[test]
+    fn test_style_only_changed_links() {
+        let mut app = App::new();
+        app.insert_resource(LinkStyleScales {
+            scale: Scale::Linear,
+            domain: None,
+            thickness: (1.0, 5.0),
+            color_ramp: ColorRamp(vec![Color::BLACK, Color::WHITE]),
+        })
+        .add_systems(Update, style_links_by_weight);
+        let light = app.world_mut().spawn(LinkWeight(1.0)).id();
+        let heavy = app.world_mut().spawn(LinkWeight(3.0)).id();
+        app.update();
+        let last_changed = |app: &App, entity: Entity| {
+            app.world()
+                .entity(entity)
+                .get_ref::<LinkStyle>()
+                .unwrap()
+                .last_changed()
+        };
+        let (light_changed, heavy_changed) = (last_changed(&app, light), last_changed(&app, heavy));
+
+        // The domain stays the same, so only the new link gets a style
+        let medium = app.world_mut().spawn(LinkWeight(2.0)).id();
+        app.update();
+        assert_eq!(last_changed(&app, light), light_changed);
+        assert_eq!(last_changed(&app, heavy), heavy_changed);
+        assert_eq!(app.world().get::<LinkStyle>(light).unwrap().thickness, 1.0);
+        assert_eq!(app.world().get::<LinkStyle>(heavy).unwrap().thickness, 5.0);
+        assert_eq!(app.world().get::<LinkStyle>(medium).unwrap().thickness, 3.0);
+
+        // Without the heaviest link, the domain shrinks
+        app.world_mut().despawn(heavy);
+        app.update();
+        assert_eq!(app.world().get::<LinkStyle>(light).unwrap().thickness, 1.0);
+        assert_eq!(app.world().get::<LinkStyle>(medium).unwrap().thickness, 5.0);
+    }
+}
@@ -0,0 +1,232 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        change_detection::{DetectChanges as _, DetectChangesMut as _},
+        entity::Entity,
+        event::{EventReader, EventWriter},
+        query::{Added, With},
+        schedule::IntoSystemConfigs as _,
+        system::{Query, Res, ResMut, Resource, Single},
+    },
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    render::{camera::OrthographicProjection, view::Visibility},
+};
+
+use super::{
+    common::{NodeLabelText, NodePhysics},
+    groups::{self, CollapseGroup, ExpandGroup, Group, MetaNode},
+    node_shape::{self, Arrowhead},
+    SimulationSet,
+};
+
+/// Scales of the orthographic projection (greater than 1.0 zooms out) above
+/// which details are left out. `None` keeps the detail at any zoom level.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct LodSettings {
+    /// Hide node labels
+    pub hide_labels: Option<f32>,
+    /// Hide arrowheads
+    pub hide_arrowheads: Option<f32>,
+    /// Draw links at most one pixel thick
+    pub thin_links: Option<f32>,
+    /// Collapse every [Group] into a meta-node
+    pub collapse_groups: Option<f32>,
+    /// Factor by which one step of the mouse wheel zooms
+    pub zoom_step: f32,
+}
+
+/// Details left out at the current zoom level, see [LodSettings].
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct LodLevel {
+    pub labels_hidden: bool,
+    pub arrowheads_hidden: bool,
+    /// Largest thickness of links in world units, if links are thinned
+    pub max_link_thickness: Option<f32>,
+    /// Groups which were collapsed because of the zoom level
+    pub collapsed_groups: Vec<String>,
+}
+
+impl LodLevel {
+    /// Thickness of a link, thinned according to the zoom level.
+    pub fn link_thickness(&self, thickness: f32) -> f32 {
+        self.max_link_thickness
+            .map_or(thickness, |max_thickness| thickness.min(max_thickness))
+    }
+}
+
+/// Plugin for leaving out details when zoomed out (level of detail), so that
+/// large graphs stay readable and fast. Scroll the mouse wheel to zoom.
+pub struct LodPlugin {
+    pub settings: LodSettings,
+}
+
+impl Default for LodPlugin {
+    fn default() -> Self {
+        Self {
+            settings: LodSettings {
+                hide_labels: Some(2.0),
+                hide_arrowheads: Some(3.0),
+                thin_links: Some(4.0),
+                collapse_groups: None,
+                zoom_step: 1.1,
+            },
+        }
+    }
+}
+
+impl Plugin for LodPlugin {
+    fn build(&self, app: &mut App) {
+        if self.settings.collapse_groups.is_some() && !app.is_plugin_added::<groups::GroupPlugin>()
+        {
+            app.add_plugins(groups::GroupPlugin);
+        }
+        app.insert_resource(self.settings)
+            .init_resource::<LodLevel>()
+            .add_systems(
+                Update,
+                (
+                    zoom_on_scroll,
+                    update_lod_level,
+                    (
+                        hide_labels,
+                        hide_arrowheads.after(node_shape::update_arrowheads),
+                    )
+                        .after(SimulationSet::Constraints),
+                )
+                    .chain(),
+            );
+        if self.settings.collapse_groups.is_some() {
+            app.add_systems(
+                Update,
+                collapse_groups_by_zoom
+                    .after(update_lod_level)
+                    .before(groups::collapse_groups),
+            );
+        }
+    }
+}
+
+/// Zoom the camera with the mouse wheel.
+pub fn zoom_on_scroll(
+    mut scroll_events: EventReader<MouseWheel>,
+    projection: Option<Single<&mut OrthographicProjection>>,
+    settings: Res<LodSettings>,
+) {
+    let Some(mut projection) = projection else {
+        return;
+    };
+    for event in scroll_events.read() {
+        let steps = match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 100.0,
+        };
+        projection.scale = (projection.scale * settings.zoom_step.powf(-steps)).clamp(0.01, 100.0);
+    }
+}
+
+/// Derive the [LodLevel] from the camera's zoom.
+pub fn update_lod_level(
+    projection: Option<Single<&OrthographicProjection>>,
+    settings: Res<LodSettings>,
+    mut level: ResMut<LodLevel>,
+) {
+    let scale = projection.map_or(1.0, |projection| projection.scale);
+    let exceeds = |threshold: Option<f32>| threshold.is_some_and(|threshold| scale > threshold);
+
+    let labels_hidden = exceeds(settings.hide_labels);
+    let arrowheads_hidden = exceeds(settings.hide_arrowheads);
+    let max_link_thickness = exceeds(settings.thin_links).then_some(scale);
+    // Avoid triggering change detection every frame
+    if level.labels_hidden != labels_hidden
+        || level.arrowheads_hidden != arrowheads_hidden
+        || level.max_link_thickness != max_link_thickness
+    {
+        level.labels_hidden = labels_hidden;
+        level.arrowheads_hidden = arrowheads_hidden;
+        level.max_link_thickness = max_link_thickness;
+    }
+}
+
+/// Show or hide node labels according to the [LodLevel].
+pub fn hide_labels(
+    mut labels_q: Query<&mut Visibility, With<NodeLabelText>>,
+    added_q: Query<(), Added<NodeLabelText>>,
+    level: Res<LodLevel>,
+) {
+    if !level.is_changed() && added_q.is_empty() {
+        return;
+    }
+    let visibility = if level.labels_hidden {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+    for mut label_visibility in &mut labels_q {
+        label_visibility.set_if_neq(visibility);
+    }
+}
+
+/// Hide arrowheads according to the [LodLevel]. They are shown again by
+/// [node_shape::update_arrowheads].
+pub fn hide_arrowheads(
+    mut arrowheads_q: Query<&mut Visibility, With<Arrowhead>>,
+    level: Res<LodLevel>,
+) {
+    if !level.arrowheads_hidden {
+        return;
+    }
+    for mut visibility in &mut arrowheads_q {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+/// Collapse every [Group] when zooming out beyond
+/// [LodSettings::collapse_groups], and expand them again when zooming in.
+pub fn collapse_groups_by_zoom(
+    projection: Option<Single<&OrthographicProjection>>,
+    nodes_q: Query<(Entity, &Group), With<NodePhysics>>,
+    meta_nodes_q: Query<(Entity, &MetaNode)>,
+    settings: Res<LodSettings>,
+    mut level: ResMut<LodLevel>,
+    mut collapse_events: EventWriter<CollapseGroup>,
+    mut expand_events: EventWriter<ExpandGroup>,
+) {
+    let Some(threshold) = settings.collapse_groups else {
+        return;
+    };
+    let scale = projection.map_or(1.0, |projection| projection.scale);
+    let collapse = scale > threshold;
+    let collapsed = !level.collapsed_groups.is_empty();
+    if collapse == collapsed {
+        return;
+    }
+
+    if collapse {
+        let mut groups = Vec::<(String, Vec<Entity>)>::new();
+        for (entity, Group(name)) in &nodes_q {
+            match groups.iter_mut().find(|(group, _)| group == name) {
+                Some((_, members)) => members.push(entity),
+                None => groups.push((name.clone(), vec![entity])),
+            }
+        }
+        level.collapsed_groups = groups.iter().map(|(name, _)| name.clone()).collect();
+        collapse_events.send_batch(groups.into_iter().map(|(name, members)| CollapseGroup {
+            members,
+            label: name.clone(),
+            group: Some(name),
+        }));
+    } else {
+        let groups = level.collapsed_groups.drain(..).collect::<Vec<_>>();
+        expand_events.send_batch(
+            meta_nodes_q
+                .iter()
+                .filter(|(_, meta_node)| {
+                    meta_node
+                        .group
+                        .as_ref()
+                        .is_some_and(|group| groups.contains(group))
+                })
+                .map(|(entity, _)| ExpandGroup(entity)),
+        );
+    }
+}
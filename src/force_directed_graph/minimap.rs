@@ -0,0 +1,331 @@
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    asset::{Assets, Handle, RenderAssetUsages},
+    color::{Alpha as _, Color, ColorToPacked as _},
+    core_pipeline::core_2d::Camera2d,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{With, Without},
+        system::{Commands, Query, Res, ResMut, Resource, Single},
+    },
+    image::Image,
+    input::{keyboard::KeyCode, ButtonInput},
+    math::{Rect, UVec2, Vec2},
+    render::{
+        camera::OrthographicProjection,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+    transform::components::Transform,
+    ui::{widget::ImageNode, BackgroundColor, Display, Interaction, Node, PositionType, Val},
+    utils::default,
+    window::{PrimaryWindow, Window},
+};
+
+use super::{
+    common::{NodeColor, NodePhysics},
+    tween::CameraTween,
+    utils::bounding_box,
+};
+
+/// Distance of the minimap to the top left corner of the window in px
+const MINIMAP_OFFSET: Vec2 = Vec2::new(8.0, 32.0);
+
+/// Marker component for the minimap overlay.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Minimap;
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MinimapSettings {
+    /// Size of the minimap in px
+    pub size: UVec2,
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct MinimapAssets {
+    pub image: Handle<Image>,
+}
+
+/// Maps the world onto the pixels of the minimap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MinimapMapping {
+    /// World area shown by the minimap, with the aspect ratio of the minimap
+    pub bounds: Rect,
+    pub size: UVec2,
+}
+
+impl MinimapMapping {
+    /// Mapping which shows all of `bounds` (plus a margin), centered.
+    pub fn new(bounds: Rect, size: UVec2) -> Self {
+        let size_f = size.as_vec2().max(Vec2::ONE);
+        let world_size = bounds.size().max(Vec2::ONE) * 1.1;
+        let pixel_size = (world_size / size_f).max_element();
+        Self {
+            bounds: Rect::from_center_size(bounds.center(), size_f * pixel_size),
+            size,
+        }
+    }
+
+    /// Pixel of the world position, with y pointing down.
+    pub fn world_to_pixel(&self, position: Vec2) -> Vec2 {
+        let t = (position - self.bounds.min) / self.bounds.size();
+        Vec2::new(t.x, 1.0 - t.y) * self.size.as_vec2()
+    }
+
+    pub fn pixel_to_world(&self, pixel: Vec2) -> Vec2 {
+        let t = pixel / self.size.as_vec2();
+        self.bounds.min + Vec2::new(t.x, 1.0 - t.y) * self.bounds.size()
+    }
+}
+
+/// Plugin for a minimap in the top left corner, which shows all nodes as
+/// points and the area visible by the camera as a rectangle. Click or drag on
+/// the minimap to move the camera there. `M` toggles the minimap.
+pub struct MinimapPlugin {
+    pub size: UVec2,
+}
+
+impl Default for MinimapPlugin {
+    fn default() -> Self {
+        Self {
+            size: UVec2::new(200, 150),
+        }
+    }
+}
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MinimapSettings { size: self.size })
+            .add_systems(Startup, setup_minimap)
+            .add_systems(
+                Update,
+                (toggle_minimap, move_camera_on_minimap, draw_minimap),
+            );
+    }
+}
+
+pub fn setup_minimap(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    settings: Res<MinimapSettings>,
+) {
+    let image = images.add(Image::new_fill(
+        Extent3d {
+            width: settings.size.x,
+            height: settings.size.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    ));
+    commands.spawn((
+        Minimap,
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(MINIMAP_OFFSET.x),
+            top: Val::Px(MINIMAP_OFFSET.y),
+            width: Val::Px(settings.size.x as f32),
+            height: Val::Px(settings.size.y as f32),
+            ..default()
+        },
+        ImageNode::new(image.clone()),
+        BackgroundColor(Color::BLACK.with_alpha(0.6)),
+        Interaction::default(),
+    ));
+    commands.insert_resource(MinimapAssets { image });
+}
+
+pub fn toggle_minimap(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut minimap_q: Query<&mut Node, With<Minimap>>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyM) {
+        return;
+    }
+    for mut node in &mut minimap_q {
+        node.display = match node.display {
+            Display::None => Display::Flex,
+            _ => Display::None,
+        };
+    }
+}
+
+/// World area shown by the minimap: all nodes and the area visible by the
+/// camera.
+fn minimap_mapping(
+    nodes: impl IntoIterator<Item = Vec2>,
+    view: Rect,
+    size: UVec2,
+) -> MinimapMapping {
+    let bounds = bounding_box(nodes).map_or(view, |bounds| bounds.union(view));
+    MinimapMapping::new(bounds, size)
+}
+
+/// Area visible by the camera in world coordinates.
+fn camera_view(transform: &Transform, projection: &OrthographicProjection) -> Rect {
+    let center = transform.translation.truncate();
+    Rect {
+        min: center + projection.area.min,
+        max: center + projection.area.max,
+    }
+}
+
+/// Move the camera to the position of the pointer while the minimap is
+/// pressed.
+pub fn move_camera_on_minimap(
+    minimap_q: Query<(&Interaction, &Node), With<Minimap>>,
+    nodes_q: Query<&Transform, (With<NodePhysics>, Without<Camera2d>)>,
+    camera: Option<Single<(&mut Transform, &OrthographicProjection), With<Camera2d>>>,
+    camera_tweens_q: Query<Entity, With<CameraTween>>,
+    window: Option<Single<&Window, With<PrimaryWindow>>>,
+    settings: Res<MinimapSettings>,
+    mut commands: Commands,
+) {
+    let (Some(camera), Some(window)) = (camera, window) else {
+        return;
+    };
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+    let pressed = minimap_q.iter().any(|(interaction, node)| {
+        *interaction == Interaction::Pressed && node.display != Display::None
+    });
+    if !pressed {
+        return;
+    }
+
+    let (mut transform, projection) = camera.into_inner();
+    let mapping = minimap_mapping(
+        nodes_q.iter().map(|node| node.translation.truncate()),
+        camera_view(&transform, projection),
+        settings.size,
+    );
+    let position = mapping.pixel_to_world(cursor - MINIMAP_OFFSET);
+    transform.translation.x = position.x;
+    transform.translation.y = position.y;
+    // Don't fight a running camera animation
+    for entity in &camera_tweens_q {
+        commands.entity(entity).remove::<CameraTween>();
+    }
+}
+
+/// Draw the nodes and the camera's view into the minimap's image.
+pub fn draw_minimap(
+    minimap_q: Query<&Node, With<Minimap>>,
+    nodes_q: Query<(&Transform, Option<&NodeColor>), (With<NodePhysics>, Without<Camera2d>)>,
+    camera: Option<Single<(&Transform, &OrthographicProjection), With<Camera2d>>>,
+    assets: Option<Res<MinimapAssets>>,
+    settings: Res<MinimapSettings>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let (Some(camera), Some(assets)) = (camera, assets) else {
+        return;
+    };
+    if minimap_q.iter().all(|node| node.display == Display::None) {
+        return;
+    }
+    let Some(image) = images.get_mut(&assets.image) else {
+        return;
+    };
+
+    let (transform, projection) = camera.into_inner();
+    let view = camera_view(transform, projection);
+    let mapping = minimap_mapping(
+        nodes_q.iter().map(|(node, _)| node.translation.truncate()),
+        view,
+        settings.size,
+    );
+    let points = nodes_q
+        .iter()
+        .map(|(node, color)| {
+            (
+                node.translation.truncate(),
+                color.map_or(Color::WHITE, |color| color.0),
+            )
+        })
+        .collect::<Vec<_>>();
+    image.data = rasterize(&mapping, &points, view);
+}
+
+/// RGBA pixels of the minimap: a 2x2 px point per node and the outline of the
+/// camera's view.
+pub fn rasterize(mapping: &MinimapMapping, points: &[(Vec2, Color)], view: Rect) -> Vec<u8> {
+    let (width, height) = (mapping.size.x as i32, mapping.size.y as i32);
+    let mut data = vec![0; (4 * width * height) as usize];
+    let mut set_pixel = |x: i32, y: i32, color: [u8; 4]| {
+        if (0..width).contains(&x) && (0..height).contains(&y) {
+            let i = (4 * (y * width + x)) as usize;
+            data[i..i + 4].copy_from_slice(&color);
+        }
+    };
+
+    for (position, color) in points {
+        let pixel = mapping.world_to_pixel(*position).floor().as_ivec2();
+        let color = color.to_srgba().to_u8_array();
+        for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            set_pixel(pixel.x + dx, pixel.y + dy, color);
+        }
+    }
+
+    let min = mapping
+        .world_to_pixel(Vec2::new(view.min.x, view.max.y))
+        .floor()
+        .as_ivec2();
+    let max = mapping
+        .world_to_pixel(Vec2::new(view.max.x, view.min.y))
+        .floor()
+        .as_ivec2();
+    let white = [255, 255, 255, 255];
+    for x in min.x..=max.x {
+        set_pixel(x, min.y, white);
+        set_pixel(x, max.y, white);
+    }
+    for y in min.y..=max.y {
+        set_pixel(min.x, y, white);
+        set_pixel(max.x, y, white);
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rasterize, MinimapMapping};
+    use bevy::{
+        color::Color,
+        math::{Rect, UVec2, Vec2},
+    };
+
+    #[test]
+    fn test_minimap_mapping() {
+        let mapping =
+            MinimapMapping::new(Rect::new(-100.0, -50.0, 100.0, 50.0), UVec2::new(100, 100));
+        // Keeps the aspect ratio of the minimap
+        assert_eq!(mapping.bounds.size().x, mapping.bounds.size().y);
+        assert_eq!(mapping.world_to_pixel(Vec2::ZERO), Vec2::new(50.0, 50.0));
+        let position = Vec2::new(30.0, -20.0);
+        let roundtrip = mapping.pixel_to_world(mapping.world_to_pixel(position));
+        assert!(roundtrip.distance(position) < 1e-3);
+        // y points down
+        assert!(mapping.world_to_pixel(Vec2::new(0.0, 40.0)).y < 50.0);
+    }
+
+    #[test]
+    fn test_rasterize() {
+        let mapping = MinimapMapping {
+            bounds: Rect::new(0.0, 0.0, 10.0, 10.0),
+            size: UVec2::new(10, 10),
+        };
+        let data = rasterize(
+            &mapping,
+            &[(Vec2::new(2.5, 7.5), Color::srgb(1.0, 0.0, 0.0))],
+            Rect::new(-20.0, -20.0, -10.0, -10.0),
+        );
+        assert_eq!(data.len(), 400);
+        let pixel = |x: usize, y: usize| &data[4 * (y * 10 + x)..4 * (y * 10 + x) + 4];
+        assert_eq!(pixel(2, 2), [255, 0, 0, 255]);
+        assert_eq!(pixel(3, 3), [255, 0, 0, 255]);
+        assert_eq!(pixel(5, 5), [0, 0, 0, 0]);
+    }
+}
@@ -1,12 +1,17 @@
 use core::f32;
 
-use bevy::app::{App, Startup, Update};
+use batching::{BatchedLink, BatchedRenderPlugin};
+use bevy::app::{App, PostUpdate, Startup, Update};
 use bevy::asset::Assets;
 use bevy::color::{Alpha as _, Color};
 use bevy::core_pipeline::core_2d::Camera2d;
 use bevy::dev_tools::fps_overlay::{FpsOverlayConfig, FpsOverlayPlugin};
-use bevy::ecs::query::{With, Without};
-use bevy::ecs::system::{Commands, Query};
+use bevy::ecs::entity::Entity;
+use bevy::ecs::query::{Added, Changed, Or, With, Without};
+use bevy::ecs::schedule::common_conditions::resource_equals;
+use bevy::ecs::schedule::{IntoSystemConfigs as _, IntoSystemSetConfigs as _, SystemSet};
+use bevy::ecs::system::{Commands, Query, Res, Resource};
+use bevy::hierarchy::{BuildChildren as _, ChildBuild as _};
 use bevy::math::primitives::{Circle, Rectangle};
 use bevy::math::{Quat, Vec2, Vec3};
 use bevy::picking::mesh_picking::MeshPickingPlugin;
@@ -14,23 +19,94 @@ use bevy::prelude::PluginGroup;
 use bevy::prelude::ResMut;
 use bevy::render::mesh::{Mesh, Mesh2d};
 use bevy::sprite::{ColorMaterial, MeshMaterial2d};
-use bevy::text::TextFont;
+use bevy::text::{Text2d, TextColor, TextFont};
 use bevy::transform::components::Transform;
-use bevy::utils::default;
+use bevy::utils::{default, HashMap};
 use bevy::window::{self, Window, WindowPlugin};
 use bevy::DefaultPlugins;
-use common::{NodeLink, NodePhysics};
+use common::{
+    LinkColor, LinkStyle, LinkWeight, NodeColor, NodeId, NodeLabel, NodeLabelText, NodeLink,
+    NodeMetadata, NodePhysics, NodeRadius,
+};
+use context_menu::ContextMenuPlugin;
+use control::SimulationControlPlugin;
+use force_debug::ForceDebugPlugin;
+use forces::fruchterman_reingold::FruchtermanReingoldRes;
+use graph_store::{GraphStore, GraphStorePlugin, StoredLink, StoredNode};
+use groups::GroupPlugin;
+use hover::HoverPlugin;
+use inspector::InspectorPlugin;
+use layouts::LayoutPlugin;
+use link_style::LinkStylePlugin;
+use lod::{LodLevel, LodPlugin};
+use minimap::MinimapPlugin;
+use node_shape::{NodeShape, NodeShapePlugin};
+use node_style::{ColorEncoding, NodeAttribute, NodeEncodings, NodeStylePlugin, SizeEncoding};
 use rand::rngs::SmallRng;
 use rand::seq::IndexedRandom as _;
 use rand::{Rng as _, SeedableRng as _};
+use search::SearchPlugin;
+use selection::SelectionPlugin;
+use snapshot::SnapshotPlugin;
+use stats::SimulationStatsPlugin;
+use svg::SvgExportPlugin;
+use tween::TweenPlugin;
 use verlet::VerletPlugin;
 
+pub mod batching;
 pub mod common;
+pub mod context_menu;
+pub mod control;
+pub mod force_debug;
 pub mod forces;
+pub mod graph_store;
+pub mod groups;
+pub mod hidden;
+pub mod hover;
+pub mod inspector;
+pub mod layouts;
+pub mod link_style;
+pub mod lod;
+pub mod minimap;
 pub mod mouse;
+pub mod node_shape;
+pub mod node_style;
+pub mod search;
+pub mod selection;
+pub mod snapshot;
+pub mod stats;
+pub mod svg;
+pub mod tween;
 pub mod utils;
 pub mod verlet;
 
+/// Order of the simulation within [Update]. Systems outside of these sets,
+/// e.g. rendering the links, should run after [SimulationSet::Constraints].
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SimulationSet {
+    /// Verlet integration step (in [bevy::app::PreUpdate]), see
+    /// [verlet::apply_velocity]
+    Integration,
+    /// Forces acting on the nodes
+    Forces,
+    /// Corrections after all forces have been applied, e.g. keeping nodes
+    /// inside the window
+    Constraints,
+}
+
+/// Selects the set of forces which lays out the graph.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ForceModel {
+    /// Link springs and repulsion, integrated via verlet integration
+    #[default]
+    Verlet,
+    /// Fruchterman–Reingold attraction/repulsion with a cooling temperature
+    FruchtermanReingold,
+    /// No simulation at all: nodes stay where a static layout placed them,
+    /// see [layouts::LayoutPlugin]
+    Static,
+}
+
 /// Run the bevy application. Blocks until the window is closed.
 pub fn run() {
     App::new()
@@ -63,8 +139,55 @@ pub fn run() {
             VerletPlugin {
                 velocity_decay: 1.0,
             },
+            // Only counted while the overlay is shown, cheap for the demo
+            SimulationStatsPlugin {
+                edge_crossings: true,
+                ..default()
+            },
+            LayoutPlugin::default(),
+            TweenPlugin::default(),
+            SnapshotPlugin::default(),
+            SvgExportPlugin::default(),
+            LinkStylePlugin::default(),
+            NodeShapePlugin::default(),
+            BatchedRenderPlugin::default(),
+            LodPlugin::default(),
+            NodeStylePlugin {
+                encodings: NodeEncodings {
+                    size: Some(SizeEncoding {
+                        attribute: NodeAttribute::Degree,
+                        scale: link_style::Scale::Linear,
+                        radius: (10.0, 25.0),
+                    }),
+                    color: Some(ColorEncoding::Categorical {
+                        attribute: NodeAttribute::Metadata("kind".to_string()),
+                        palette: node_style::default_palette(),
+                    }),
+                },
+            },
         ))
-        .add_systems(Startup, setup)
+        // Interaction
+        .add_plugins((
+            SelectionPlugin,
+            HoverPlugin::default(),
+            InspectorPlugin,
+            SearchPlugin::default(),
+            ContextMenuPlugin::default(),
+            GraphStorePlugin::default(),
+            GroupPlugin,
+            MinimapPlugin::default(),
+            SimulationControlPlugin,
+            ForceDebugPlugin::default(),
+        ))
+        .configure_sets(
+            Update,
+            (SimulationSet::Forces, SimulationSet::Constraints).chain(),
+        )
+        .init_resource::<ForceModel>()
+        .init_resource::<FruchtermanReingoldRes>()
+        // E.g. `setup(Some(25))` to start with half of the graph and explore
+        // the rest by double-clicking nodes
+        .add_systems(Startup, setup(None))
         .add_systems(
             Update,
             (
@@ -72,14 +195,59 @@ pub fn run() {
                 forces::friction::apply_friction(400.0),
                 //forces::cutoff_force::apply_cutoff_force(100.0),
                 //forces::gravity::apply_gravity_force(200000.0),
-                forces::mean_to_center::apply_mean_to_center,
-                forces::link::apply_link_force(1000.0, f32::MAX),
+                // Softer than the former 1000: all forces now act on the same
+                // positions before integration, and with 1000 the springs
+                // overshoot and the demo graph diverges at 60 fps
+                forces::link::apply_link_force(500.0, f32::MAX, false),
                 forces::repulsion::apply_repulsion_force(20000000.0),
                 //forces::galaxy::apply_galaxy_force(1.5),
-                forces::window_border::apply_window_border(0.5),
-                update_links,
-            ),
+            )
+                .in_set(SimulationSet::Forces)
+                .run_if(resource_equals(ForceModel::Verlet)),
+        )
+        .add_systems(
+            Update,
+            (
+                forces::fruchterman_reingold::reheat_fruchterman_reingold
+                    .before(SimulationSet::Forces),
+                forces::fruchterman_reingold::apply_fruchterman_reingold(1.0)
+                    .in_set(SimulationSet::Forces),
+            )
+                .run_if(resource_equals(ForceModel::FruchtermanReingold)),
+        )
+        .add_systems(
+            Update,
+            (
+                forces::layer_target::apply_layer_target_force(2000.0),
+                forces::mean_to_center::apply_mean_to_center,
+            )
+                .in_set(SimulationSet::Forces),
         )
+        .add_systems(
+            Update,
+            (
+                force_debug::record_constraint(
+                    forces::fruchterman_reingold::LIMIT_LABEL,
+                    forces::fruchterman_reingold::limit_fruchterman_reingold_displacement(
+                        0.99, 1.0,
+                    ),
+                )
+                .run_if(resource_equals(ForceModel::FruchtermanReingold)),
+                force_debug::record_constraint(
+                    forces::collision::LABEL,
+                    forces::collision::apply_collision(2.0),
+                ),
+                force_debug::record_constraint(
+                    forces::window_border::LABEL,
+                    forces::window_border::apply_window_border(0.5),
+                ),
+                force_debug::record_constraint(forces::pinned::LABEL, forces::pinned::apply_pinned),
+            )
+                .chain()
+                .in_set(SimulationSet::Constraints),
+        )
+        .add_systems(Update, update_links.after(SimulationSet::Constraints))
+        .add_systems(PostUpdate, (apply_node_colors, apply_link_colors))
         .run();
 }
 
@@ -107,95 +275,223 @@ fn _setup_simple(
         .observe(mouse::drag_end);
 }
 
-/// Spawn camera, nodes, and links
-fn setup(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    let mut rng = SmallRng::seed_from_u64(0);
+/// Spawn a node entity with its appearance, physics and observers. Its
+/// material is added by [apply_node_colors], unless the node is batched.
+pub fn spawn_node(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    node: &StoredNode,
+    radius: NodeRadius,
+    transform: Transform,
+) -> Entity {
+    let mesh = meshes.add(node.shape.mesh(radius.0));
 
-    commands.spawn(Camera2d);
+    // Spawn the node entity with its components (Sprite, Mesh2d, etc.)
+    commands
+        .spawn((
+            // Actual appearance
+            Mesh2d(mesh),
+            NodeColor(node.color),
+            node.shape.clone(),
+            // X/Y position
+            transform,
+            // Additional physics information: previous position to approximate velocity for inertia
+            NodePhysics::from_transform(transform),
+            // Stable identity, e.g. for saving the layout
+            NodeId(node.id.clone()),
+            NodeLabel(node.label.clone()),
+            NodeMetadata(node.metadata.clone()),
+            radius,
+        ))
+        .with_children(|parent| {
+            // Label on top of the node
+            parent.spawn((
+                NodeLabelText,
+                Text2d::new(node.label.clone()),
+                TextFont {
+                    font_size: 10.0,
+                    ..default()
+                },
+                TextColor(Color::BLACK.with_alpha(0.7)),
+                Transform::from_xyz(0.0, 0.0, 0.5),
+            ));
+        })
+        .observe(mouse::drag_n_drop)
+        .observe(mouse::drag_start)
+        .observe(mouse::drag_end)
+        .observe(selection::select_on_click)
+        .observe(context_menu::open_context_menu)
+        .observe(graph_store::toggle_on_double_click)
+        .observe(hover::highlight_neighborhood)
+        .observe(hover::clear_highlight)
+        .id()
+}
+
+/// Spawn a link entity between two nodes. Its material is added by
+/// [apply_link_colors], unless the link is batched.
+pub fn spawn_link(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    source: Entity,
+    target: Entity,
+    target_distance: f32,
+    weight: LinkWeight,
+) -> Entity {
+    let style = LinkStyle::default();
+    commands
+        .spawn((
+            NodeLink {
+                // The two nodes to be linked
+                source,
+                target,
+                // Target distance for the link force
+                target_distance,
+            },
+            // Thickness and color, see [link_style]
+            weight,
+            style,
+            LinkColor(style.color),
+            // Rectangle dimensions will be transformed later in [update_links]
+            Mesh2d(meshes.add(Rectangle::new(1.0, 1.0))),
+            // Position will be transformed later [uodate_links]
+            Transform::default(),
+        ))
+        .id()
+}
+
+/// Spawn camera, nodes, and links. All nodes are kept in the [GraphStore]. If
+/// `seed_nodes` is given, only that many of them are spawned at first, the rest
+/// can be explored by double-clicking nodes (see [graph_store]).
+fn setup(seed_nodes: Option<usize>) -> impl Fn(Commands<'_, '_>, ResMut<'_, Assets<Mesh>>) {
+    move |mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>| {
+        let mut rng = SmallRng::seed_from_u64(0);
+        // Separate, so the weights don't change the random graph
+        let mut weight_rng = SmallRng::seed_from_u64(1);
+
+        commands.spawn(Camera2d);
 
-    // For every node ("entity")
-    let num_entities: u16 = 50;
-    let entities = (0..num_entities)
-        .map(|i| {
-            let radius = 15.0;
-            let shape = meshes.add(Circle::new(radius));
+        let mut store = GraphStore::default();
 
+        // For every node ("entity")
+        let num_entities: u16 = 50;
+        let kinds = [
+            ("service", NodeShape::Circle),
+            ("database", NodeShape::Rectangle { aspect_ratio: 1.0 }),
+            (
+                "queue",
+                NodeShape::RoundedRectangle {
+                    aspect_ratio: 1.6,
+                    corner_radius: 0.5,
+                },
+            ),
+            ("cache", NodeShape::Polygon { sides: 4 }),
+            ("client", NodeShape::Polygon { sides: 6 }),
+        ];
+        let mut transforms = Vec::new();
+        for i in 0..num_entities {
             // Distribute colors evenly across the rainbow.
-            let color = Color::hsl(360. * i as f32 / num_entities as f32, 0.95, 0.7);
+            let hue = 360. * i as f32 / num_entities as f32;
+            let (kind, shape) = &kinds[usize::from(i) % kinds.len()];
+            store.add_node(StoredNode {
+                id: i.to_string(),
+                label: i.to_string(),
+                metadata: vec![
+                    ("hue".to_string(), format!("{:.0}°", hue)),
+                    ("kind".to_string(), kind.to_string()),
+                ],
+                color: Color::hsl(hue, 0.95, 0.7),
+                shape: shape.clone(),
+            });
 
             // Start position ("transform") in the center (but start slightly random)
-            let transform = Transform::from_xyz(
+            transforms.push(Transform::from_xyz(
                 rng.random_range(-100.0..100.0),
                 rng.random_range(-100.0..100.0),
                 rng.random_range(0.0..1.0),
-            );
-
-            // Spawn the node entity with its components (Sprite, Mesh2d, etc.)
-            commands
-                .spawn((
-                    // Actual appearance
-                    Mesh2d(shape),
-                    MeshMaterial2d(materials.add(color)),
-                    // X/Y position
-                    transform,
-                    // Additional physics information: previous position to approximate velocity for inertia
-                    NodePhysics::from_transform(transform),
-                ))
-                .observe(mouse::drag_n_drop)
-                .observe(mouse::drag_start)
-                .observe(mouse::drag_end)
-                .id()
-        })
-        .collect::<Vec<_>>();
-
-    // Create random links between nodes
-    for _ in 0..(f32::from(num_entities) * 1.2) as u32 {
-        let a = entities.choose(&mut rng).unwrap();
-        let b = entities.choose(&mut rng).unwrap();
-        if a == b {
-            continue;
+            ));
         }
 
-        commands.spawn((
-            NodeLink {
-                // The two nodes to be linked
-                source: *a,
-                target: *b,
-                // Target distance for the link force
+        // Create random links between nodes
+        let ids = (0..num_entities).collect::<Vec<_>>();
+        for _ in 0..(f32::from(num_entities) * 1.2) as u32 {
+            let a = ids.choose(&mut rng).unwrap();
+            let b = ids.choose(&mut rng).unwrap();
+            if a == b {
+                continue;
+            }
+
+            store.add_link(StoredLink {
+                source: a.to_string(),
+                target: b.to_string(),
                 target_distance: rng.random_range(50.0..150.0),
-            },
-            // Rectangle dimensions will be transformed later in [update_links]
-            Mesh2d(meshes.add(Rectangle::new(1.0, 1.0))),
-            MeshMaterial2d(materials.add(Color::srgba(1.0, 1.0, 1.0, 0.5))),
-            // Position will be transformed later [uodate_links]
-            Transform::default(),
-        ));
+                weight: weight_rng.random_range(1.0..10.0),
+            });
+        }
+
+        // Spawn the seed nodes
+        let mut entities = HashMap::new();
+        let num_seed_nodes = seed_nodes.unwrap_or(store.nodes.len());
+        for (node, transform) in store.nodes.iter().zip(transforms).take(num_seed_nodes) {
+            let entity = spawn_node(
+                &mut commands,
+                &mut meshes,
+                node,
+                NodeRadius::default(),
+                transform,
+            );
+            entities.insert(node.id.clone(), entity);
+        }
+        for link in &store.links {
+            if let (Some(&source), Some(&target)) =
+                (entities.get(&link.source), entities.get(&link.target))
+            {
+                spawn_link(
+                    &mut commands,
+                    &mut meshes,
+                    source,
+                    target,
+                    link.target_distance,
+                    LinkWeight(link.weight),
+                );
+            }
+        }
+
+        commands.insert_resource(store);
     }
 }
 
-/// Update the links between nodes (position and rotation)
+/// Update the links between nodes (position and rotation). Links end at the
+/// boundary of the nodes' [NodeShape]. Batched links are skipped, see
+/// [batching::update_link_batch].
 fn update_links(
-    mut links_q: Query<(&NodeLink, &mut Transform), Without<NodePhysics>>,
-    transforms_q: Query<&Transform, With<NodePhysics>>,
+    mut links_q: Query<
+        (&NodeLink, Option<&LinkStyle>, &mut Transform),
+        (Without<NodePhysics>, Without<BatchedLink>),
+    >,
+    nodes_q: Query<(&Transform, Option<&NodeShape>, Option<&NodeRadius>), With<NodePhysics>>,
+    lod_level: Option<Res<LodLevel>>,
 ) {
-    for (link, mut link_transform) in &mut links_q {
-        let source_transform = transforms_q.get(link.source).unwrap();
-        let target_transform = transforms_q.get(link.target).unwrap();
+    for (link, link_style, mut link_transform) in &mut links_q {
+        let (source_transform, source_shape, source_radius) = nodes_q.get(link.source).unwrap();
+        let (target_transform, target_shape, target_radius) = nodes_q.get(link.target).unwrap();
 
         // Update the link's transform to match the source and target positions
-        let source_position = source_transform.translation.truncate();
-        let target_position = target_transform.translation.truncate();
+        let (source_position, target_position) = node_shape::clipped_endpoints(
+            source_transform.translation.truncate(),
+            (source_shape, source_radius),
+            target_transform.translation.truncate(),
+            (target_shape, target_radius),
+        );
 
         let midpoint = (source_position + target_position) / 2.0;
         let direction = target_position - source_position;
         let angle = Vec2::X.angle_to(direction);
         let length = direction.length();
 
-        let thickness = 2.5;
+        let thickness = link_style.copied().unwrap_or_default().thickness;
+        let thickness = lod_level
+            .as_deref()
+            .map_or(thickness, |level| level.link_thickness(thickness));
 
         // Update the link's transform to match the source and target positions
         link_transform.translation = midpoint.extend(0.0);
@@ -203,3 +499,56 @@ fn update_links(
         link_transform.scale = Vec3::new(length, thickness, 1.0);
     }
 }
+
+/// Apply the [NodeColor] to the material of nodes with a mesh of their own.
+/// Nodes get their material here, once it is clear that they are not batched
+/// (see [batching::batch_nodes]).
+pub fn apply_node_colors(
+    nodes_q: Query<
+        (
+            Entity,
+            &NodeColor,
+            Option<&NodeShape>,
+            Option<&MeshMaterial2d<ColorMaterial>>,
+        ),
+        (With<Mesh2d>, Or<(Changed<NodeColor>, Added<Mesh2d>)>),
+    >,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut commands: Commands,
+) {
+    for (entity, color, shape, material) in &nodes_q {
+        match material.and_then(|material| materials.get_mut(&material.0)) {
+            Some(material) => material.color = color.0,
+            None => {
+                let material = materials.add(ColorMaterial {
+                    color: color.0,
+                    texture: shape.and_then(NodeShape::texture),
+                    ..default()
+                });
+                commands.entity(entity).insert(MeshMaterial2d(material));
+            }
+        }
+    }
+}
+
+/// Apply the [LinkColor] to the material of links with a mesh of their own.
+/// Links get their material here, once it is clear that they are not batched
+/// (see [batching::batch_links]).
+pub fn apply_link_colors(
+    links_q: Query<
+        (Entity, &LinkColor, Option<&MeshMaterial2d<ColorMaterial>>),
+        (With<Mesh2d>, Or<(Changed<LinkColor>, Added<Mesh2d>)>),
+    >,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut commands: Commands,
+) {
+    for (entity, color, material) in &links_q {
+        match material.and_then(|material| materials.get_mut(&material.0)) {
+            Some(material) => material.color = color.0,
+            None => {
+                let material = materials.add(color.0);
+                commands.entity(entity).insert(MeshMaterial2d(material));
+            }
+        }
+    }
+}
@@ -1,57 +1,93 @@
 use bevy::{
     ecs::{
+        entity::Entity,
         observer::Trigger,
+        query::{Has, With},
         system::{Commands, Query},
     },
-    math::{Vec2, Vec3},
+    math::Vec2,
     picking::events::{Drag, DragEnd, DragStart, Pointer},
     render::camera::Camera,
     transform::components::{GlobalTransform, Transform},
 };
 
-use crate::force_directed_graph::{common::MouseLocked, utils::ClampF32Range};
+use crate::force_directed_graph::{
+    common::{MouseLocked, Pinned},
+    utils::ClampF32Range,
+};
 
-use super::common::NodePhysics;
+use super::{common::NodePhysics, selection::Selected, tween::Tween};
 
 /// Observer for drag-and-drop events. Requires a sprite for now. Moves the
-/// entity (node) to the mouse position.
+/// entity (node) to the mouse position. If the node is selected, all selected
+/// nodes are moved along. Pinned nodes are pinned to the new position.
 pub fn drag_n_drop(
     trigger: Trigger<Pointer<Drag>>,
-    mut transforms_q: Query<&mut Transform>,
+    mut transforms_q: Query<(&mut Transform, Option<&mut Pinned>, Has<Selected>)>,
+    selected_q: Query<Entity, With<Selected>>,
     camera_q: Query<(&Camera, &GlobalTransform)>,
 ) {
-    let mut transform = transforms_q.get_mut(trigger.entity()).unwrap();
+    let (transform, _, selected) = transforms_q.get(trigger.entity()).unwrap();
 
     let (camera, camera_transform) = camera_q.get_single().expect("Expected a single camera");
     let world_pos = camera
         .viewport_to_world_2d(camera_transform, trigger.event().pointer_location.position)
         .expect("Camera's projection matrix is invalid");
-    transform.translation = Vec3::new(world_pos.x, world_pos.y, transform.translation.z);
+    let delta = world_pos - transform.translation.truncate();
+
+    let entities = if selected {
+        selected_q.iter().collect()
+    } else {
+        vec![trigger.entity()]
+    };
+    for entity in entities {
+        let Ok((mut transform, pinned, _)) = transforms_q.get_mut(entity) else {
+            continue;
+        };
+        transform.translation = (transform.translation + delta.extend(0.0)).clamp_f32_range();
+        if let Some(mut pinned) = pinned {
+            pinned.position = transform.translation.truncate();
+        }
+    }
 }
 
 /// Observer for drag-and-drop events. Adds a `MouseLocked` component to the
-/// node entity. This is needed for disabling forces and inertia.
-pub fn drag_start(trigger: Trigger<Pointer<DragStart>>, mut commands: Commands) {
-    commands.entity(trigger.event().target).insert(MouseLocked {
-        velocity: Vec2::ZERO,
-    });
+/// node entity (or all selected nodes, if the node is selected). This is
+/// needed for disabling forces and inertia. A running animation of the node is
+/// cancelled.
+pub fn drag_start(
+    trigger: Trigger<Pointer<DragStart>>,
+    selected_q: Query<Entity, With<Selected>>,
+    mut commands: Commands,
+) {
+    let entities = if selected_q.contains(trigger.event().target) {
+        selected_q.iter().collect()
+    } else {
+        vec![trigger.event().target]
+    };
+    for entity in entities {
+        commands
+            .entity(entity)
+            .insert(MouseLocked {
+                velocity: Vec2::ZERO,
+            })
+            .remove::<Tween>();
+    }
 }
 
 /// Observer for drag-and-drop events. Removes the `MouseLocked` component from
-/// the node entity. See [drag_start].
+/// all locked node entities. See [drag_start].
 pub fn drag_end(
-    trigger: Trigger<Pointer<DragEnd>>,
-    mut transforms_q: Query<(&mut NodePhysics, &MouseLocked)>,
+    _trigger: Trigger<Pointer<DragEnd>>,
+    mut transforms_q: Query<(Entity, &mut NodePhysics, &MouseLocked)>,
     mut commands: Commands,
 ) {
-    let (mut node_physics, mouse_locked) = transforms_q.get_mut(trigger.entity()).unwrap();
-
-    // since verlet integration was locked, position is equal to previous position
-    // manipulate previous position to achive mouse velocity
-    node_physics.previous_position =
-        (node_physics.previous_position - mouse_locked.velocity).clamp_f32_range();
+    for (entity, mut node_physics, mouse_locked) in &mut transforms_q {
+        // since verlet integration was locked, position is equal to previous position
+        // manipulate previous position to achive mouse velocity
+        node_physics.previous_position =
+            (node_physics.previous_position - mouse_locked.velocity).clamp_f32_range();
 
-    commands
-        .entity(trigger.event().target)
-        .remove::<MouseLocked>();
+        commands.entity(entity).remove::<MouseLocked>();
+    }
 }
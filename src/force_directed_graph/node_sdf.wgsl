@@ -0,0 +1,20 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+// Circles drawn as quads: the distance to the circle's edge is computed per
+// fragment, so circles are smooth at any zoom level.
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+#ifdef VERTEX_COLORS
+    var color = mesh.color;
+#else
+    var color = vec4<f32>(1.0);
+#endif
+    // The UVs span [0, 1] over the quad, which the circle touches
+    let distance = length(mesh.uv * 2.0 - 1.0) - 1.0;
+    let width = max(fwidth(distance), 0.0001);
+    color.a *= 1.0 - smoothstep(-width, 0.0, distance);
+    if color.a <= 0.0 {
+        discard;
+    }
+    return color;
+}
@@ -0,0 +1,437 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use bevy::{
+    app::{App, Plugin, PostUpdate, Startup, Update},
+    asset::{Assets, Handle, RenderAssetUsages},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{Added, With, Without},
+        schedule::IntoSystemConfigs as _,
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    image::Image,
+    math::{
+        primitives::{Circle, Rectangle, RegularPolygon, Triangle2d},
+        Quat, Vec2, Vec3,
+    },
+    picking::PickingBehavior,
+    render::{
+        mesh::{Indices, Mesh, Mesh2d, PrimitiveTopology},
+        view::Visibility,
+    },
+    sprite::{ColorMaterial, MeshMaterial2d},
+    transform::components::Transform,
+};
+
+use super::{
+    common::{LinkStyle, NodeLink, NodePhysics, NodeRadius},
+    SimulationSet,
+};
+
+/// Number of segments per corner of a [NodeShape::RoundedRectangle].
+const CORNER_SEGMENTS: usize = 6;
+
+/// Outline of a node, sized by its [NodeRadius]. Nodes without a shape are
+/// circles. Links and arrowheads end at the shape's boundary.
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+pub enum NodeShape {
+    #[default]
+    Circle,
+    /// Rectangle which is twice the radius high and `aspect_ratio` times as
+    /// wide
+    Rectangle { aspect_ratio: f32 },
+    /// Like [NodeShape::Rectangle], with corners rounded by `corner_radius`
+    /// times the node's radius
+    RoundedRectangle {
+        aspect_ratio: f32,
+        corner_radius: f32,
+    },
+    /// Regular polygon with the radius as circumradius and a vertex at the
+    /// top, e.g. 4 sides for a diamond
+    Polygon { sides: u32 },
+    /// Square texture, e.g. an icon loaded via the `AssetServer`. The node's
+    /// color tints the image.
+    Image(Handle<Image>),
+}
+
+impl NodeShape {
+    /// Mesh of the shape for the given radius.
+    pub fn mesh(&self, radius: f32) -> Mesh {
+        match self {
+            NodeShape::Circle => Circle::new(radius).into(),
+            NodeShape::Rectangle { aspect_ratio } => {
+                Rectangle::new(2.0 * radius * aspect_ratio, 2.0 * radius).into()
+            }
+            NodeShape::RoundedRectangle {
+                aspect_ratio,
+                corner_radius,
+            } => rounded_rectangle_mesh(
+                Vec2::new(radius * aspect_ratio, radius),
+                radius * corner_radius,
+            ),
+            NodeShape::Polygon { sides } => RegularPolygon::new(radius, (*sides).max(3)).into(),
+            NodeShape::Image(_) => Rectangle::new(2.0 * radius, 2.0 * radius).into(),
+        }
+    }
+
+    /// Texture of the shape, if any.
+    pub fn texture(&self) -> Option<Handle<Image>> {
+        match self {
+            NodeShape::Image(image) => Some(image.clone()),
+            _ => None,
+        }
+    }
+
+    /// Distance from the node's center to its boundary in the given direction.
+    pub fn boundary_distance(&self, radius: f32, direction: Vec2) -> f32 {
+        let direction = direction.normalize_or(Vec2::X);
+        match self {
+            NodeShape::Circle => radius,
+            NodeShape::Rectangle { aspect_ratio } => {
+                rectangle_distance(Vec2::new(radius * aspect_ratio, radius), direction)
+            }
+            NodeShape::RoundedRectangle {
+                aspect_ratio,
+                corner_radius,
+            } => {
+                let half_size = Vec2::new(radius * aspect_ratio, radius);
+                let corner_radius = (radius * corner_radius).clamp(0.0, half_size.min_element());
+                let direction = direction.abs();
+                let distance = rectangle_distance(half_size, direction);
+                let corner_center = half_size - corner_radius;
+                let point = direction * distance;
+                if point.x <= corner_center.x || point.y <= corner_center.y {
+                    return distance;
+                }
+                // The ray hits the rounded corner: intersect it with the
+                // corner's circle and take the far intersection
+                let projection = direction.dot(corner_center);
+                let discriminant = projection * projection - corner_center.length_squared()
+                    + corner_radius * corner_radius;
+                projection + discriminant.max(0.0).sqrt()
+            }
+            NodeShape::Polygon { sides } => {
+                let sides = (*sides).max(3) as f32;
+                let apothem = radius * (PI / sides).cos();
+                // Angle to the normal of the nearest edge. The first edge lies
+                // between the top vertex and the next one counterclockwise.
+                let step = TAU / sides;
+                let angle = direction.to_angle() - (FRAC_PI_2 + step / 2.0);
+                let angle = angle - step * (angle / step).round();
+                apothem / angle.cos()
+            }
+            NodeShape::Image(_) => rectangle_distance(Vec2::splat(radius), direction),
+        }
+    }
+}
+
+/// Distance from the center of a rectangle to its boundary in the given
+/// (normalized) direction.
+fn rectangle_distance(half_size: Vec2, direction: Vec2) -> f32 {
+    let direction = direction.abs();
+    let x = if direction.x > 0.0 {
+        half_size.x / direction.x
+    } else {
+        f32::MAX
+    };
+    let y = if direction.y > 0.0 {
+        half_size.y / direction.y
+    } else {
+        f32::MAX
+    };
+    x.min(y)
+}
+
+/// Triangle fan around the center of a rectangle with rounded corners.
+fn rounded_rectangle_mesh(half_size: Vec2, corner_radius: f32) -> Mesh {
+    let corner_radius = corner_radius.clamp(0.0, half_size.min_element());
+    let corner_center = half_size - corner_radius;
+    let mut outline = Vec::with_capacity(4 * (CORNER_SEGMENTS + 1));
+    for (corner, sign) in [(1.0, 1.0), (-1.0, 1.0), (-1.0, -1.0), (1.0, -1.0)]
+        .into_iter()
+        .enumerate()
+    {
+        let center = corner_center * Vec2::new(sign.0, sign.1);
+        for i in 0..=CORNER_SEGMENTS {
+            let angle = (corner as f32 + i as f32 / CORNER_SEGMENTS as f32) * FRAC_PI_2;
+            outline.push(center + Vec2::from_angle(angle) * corner_radius);
+        }
+    }
+
+    let positions = std::iter::once(Vec2::ZERO)
+        .chain(outline.iter().copied())
+        .collect::<Vec<_>>();
+    let uvs = positions
+        .iter()
+        .map(|position| {
+            let uv = *position / half_size * 0.5 + 0.5;
+            [uv.x, 1.0 - uv.y]
+        })
+        .collect::<Vec<_>>();
+    let indices = (0..outline.len() as u32)
+        .flat_map(|i| [0, i + 1, (i + 1) % outline.len() as u32 + 1])
+        .collect::<Vec<_>>();
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        positions
+            .iter()
+            .map(|position| [position.x, position.y, 0.0])
+            .collect::<Vec<_>>(),
+    )
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_NORMAL,
+        vec![[0.0, 0.0, 1.0]; positions.len()],
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Points where a link between two nodes leaves the source's and enters the
+/// target's shape.
+pub fn clipped_endpoints(
+    source: Vec2,
+    source_shape: (Option<&NodeShape>, Option<&NodeRadius>),
+    target: Vec2,
+    target_shape: (Option<&NodeShape>, Option<&NodeRadius>),
+) -> (Vec2, Vec2) {
+    let direction = target - source;
+    let distance = direction.length();
+    let boundary = |(shape, radius): (Option<&NodeShape>, Option<&NodeRadius>), direction| {
+        shape
+            .unwrap_or(&NodeShape::Circle)
+            .boundary_distance(radius.copied().unwrap_or_default().0, direction)
+    };
+    let source_boundary = boundary(source_shape, direction);
+    let target_boundary = boundary(target_shape, -direction);
+    // Overlapping nodes: nothing is visible of the link
+    if distance <= source_boundary + target_boundary {
+        let midpoint = (source + target) / 2.0;
+        return (midpoint, midpoint);
+    }
+    let direction = direction / distance;
+    (
+        source + direction * source_boundary,
+        target - direction * target_boundary,
+    )
+}
+
+/// Arrowhead at the target end of a link. It is a separate entity (and not a
+/// child), as links are scaled to their length.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Arrowhead {
+    pub link: Entity,
+}
+
+/// Component for links which have an [Arrowhead].
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkArrowhead(pub Entity);
+
+/// Settings of the arrowheads, see [NodeShapePlugin].
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct ArrowheadSettings {
+    pub enabled: bool,
+    /// Length of an arrowhead in px, in addition to the link's thickness
+    pub size: f32,
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct ArrowheadAssets {
+    pub mesh: Handle<Mesh>,
+}
+
+/// Plugin for drawing an arrowhead at the target end of every link. Links and
+/// arrowheads are clipped to the [NodeShape] of their nodes.
+pub struct NodeShapePlugin {
+    pub arrowheads: bool,
+    pub arrowhead_size: f32,
+}
+
+impl Default for NodeShapePlugin {
+    fn default() -> Self {
+        Self {
+            arrowheads: true,
+            arrowhead_size: 8.0,
+        }
+    }
+}
+
+impl Plugin for NodeShapePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ArrowheadSettings {
+            enabled: self.arrowheads,
+            size: self.arrowhead_size,
+        })
+        .add_systems(Startup, setup_arrowheads)
+        .add_systems(Update, update_arrowheads.after(SimulationSet::Constraints))
+        .add_systems(PostUpdate, spawn_arrowheads.after(super::apply_link_colors));
+    }
+}
+
+pub fn setup_arrowheads(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+    // Tip at the origin, pointing along the X axis
+    let mesh = meshes.add(Triangle2d::new(
+        Vec2::ZERO,
+        Vec2::new(-1.0, 0.5),
+        Vec2::new(-1.0, -0.5),
+    ));
+    commands.insert_resource(ArrowheadAssets { mesh });
+}
+
+/// Spawn an [Arrowhead] for links once they get their material (see
+/// [super::apply_link_colors]). It shares the link's material, so it has the
+/// link's color. Batched links don't get a material, their arrowheads are
+/// drawn by the [LinkBatch](super::batching::LinkBatch).
+pub fn spawn_arrowheads(
+    links_q: Query<
+        (Entity, &MeshMaterial2d<ColorMaterial>),
+        (
+            Added<MeshMaterial2d<ColorMaterial>>,
+            With<NodeLink>,
+            Without<LinkArrowhead>,
+        ),
+    >,
+    settings: Res<ArrowheadSettings>,
+    assets: Res<ArrowheadAssets>,
+    mut commands: Commands,
+) {
+    if !settings.enabled {
+        return;
+    }
+    for (link, material) in &links_q {
+        let arrowhead = commands
+            .spawn((
+                Arrowhead { link },
+                Mesh2d(assets.mesh.clone()),
+                material.clone(),
+                Transform::default(),
+                Visibility::Hidden,
+                PickingBehavior::IGNORE,
+            ))
+            .id();
+        commands.entity(link).insert(LinkArrowhead(arrowhead));
+    }
+}
+
+/// Move the arrowheads to the target end of their links. Arrowheads of hidden
+/// links are hidden, those of despawned links are despawned.
+pub fn update_arrowheads(
+    mut arrowheads_q: Query<(Entity, &Arrowhead, &mut Transform, &mut Visibility)>,
+    links_q: Query<(Option<&NodeLink>, Option<&LinkStyle>), Without<Arrowhead>>,
+    nodes_q: Query<
+        (&Transform, Option<&NodeShape>, Option<&NodeRadius>),
+        (With<NodePhysics>, Without<Arrowhead>),
+    >,
+    settings: Res<ArrowheadSettings>,
+    mut commands: Commands,
+) {
+    for (entity, arrowhead, mut transform, mut visibility) in &mut arrowheads_q {
+        let Ok((link, style)) = links_q.get(arrowhead.link) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        let nodes = link.and_then(|link| {
+            Some((
+                nodes_q.get(link.source).ok()?,
+                nodes_q.get(link.target).ok()?,
+            ))
+        });
+        let Some(((source, source_shape, source_radius), (target, target_shape, target_radius))) =
+            nodes.filter(|_| settings.enabled)
+        else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+
+        let (start, end) = clipped_endpoints(
+            source.translation.truncate(),
+            (source_shape, source_radius),
+            target.translation.truncate(),
+            (target_shape, target_radius),
+        );
+        let thickness = style.copied().unwrap_or_default().thickness;
+        let size = settings.size + thickness;
+        if start.distance(end) < size {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        *visibility = Visibility::Inherited;
+        transform.translation = end.extend(0.0);
+        transform.rotation = Quat::from_rotation_z(Vec2::X.angle_to(end - start));
+        transform.scale = Vec3::new(size, size, 1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clipped_endpoints, NodeShape};
+    use crate::force_directed_graph::common::NodeRadius;
+    use bevy::math::Vec2;
+
+    #[test]
+    fn test_boundary_distance() {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+        let diagonal = Vec2::ONE;
+
+        assert!(close(
+            NodeShape::Circle.boundary_distance(10.0, diagonal),
+            10.0
+        ));
+
+        let square = NodeShape::Rectangle { aspect_ratio: 1.0 };
+        assert!(close(square.boundary_distance(10.0, Vec2::X), 10.0));
+        assert!(close(
+            square.boundary_distance(10.0, diagonal),
+            10.0 * 2f32.sqrt()
+        ));
+
+        let wide = NodeShape::Rectangle { aspect_ratio: 2.0 };
+        assert!(close(wide.boundary_distance(10.0, -Vec2::X), 20.0));
+        assert!(close(wide.boundary_distance(10.0, Vec2::Y), 10.0));
+
+        // Fully rounded square is a circle
+        let rounded = NodeShape::RoundedRectangle {
+            aspect_ratio: 1.0,
+            corner_radius: 1.0,
+        };
+        assert!(close(rounded.boundary_distance(10.0, diagonal), 10.0));
+        assert!(close(rounded.boundary_distance(10.0, Vec2::X), 10.0));
+
+        // Diamond: vertices on the axes, edges in between
+        let diamond = NodeShape::Polygon { sides: 4 };
+        assert!(close(diamond.boundary_distance(10.0, Vec2::Y), 10.0));
+        assert!(close(diamond.boundary_distance(10.0, -Vec2::X), 10.0));
+        assert!(close(
+            diamond.boundary_distance(10.0, diagonal),
+            10.0 / 2f32.sqrt()
+        ));
+    }
+
+    #[test]
+    fn test_clipped_endpoints() {
+        let radius = NodeRadius(10.0);
+        let square = NodeShape::Rectangle { aspect_ratio: 1.0 };
+        let (start, end) = clipped_endpoints(
+            Vec2::ZERO,
+            (None, Some(&radius)),
+            Vec2::new(100.0, 0.0),
+            (Some(&square), Some(&radius)),
+        );
+        assert_eq!(start, Vec2::new(10.0, 0.0));
+        assert_eq!(end, Vec2::new(90.0, 0.0));
+
+        // Overlapping nodes
+        let (start, end) = clipped_endpoints(
+            Vec2::ZERO,
+            (None, Some(&radius)),
+            Vec2::new(10.0, 0.0),
+            (None, Some(&radius)),
+        );
+        assert_eq!(start, end);
+    }
+}
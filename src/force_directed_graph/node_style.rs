@@ -0,0 +1,393 @@
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    asset::Assets,
+    color::{Alpha as _, Color},
+    ecs::{
+        change_detection::{DetectChanges as _, DetectChangesMut as _},
+        component::Component,
+        entity::Entity,
+        query::{Changed, With, Without},
+        system::{Commands, Query, Res, ResMut, Resource, Single, SystemParam},
+    },
+    hierarchy::{BuildChildren as _, ChildBuild as _, DespawnRecursiveExt as _},
+    render::mesh::{Mesh, Mesh2d},
+    text::{TextColor, TextFont},
+    ui::{
+        widget::Text, AlignItems, BackgroundColor, Display, FlexDirection, Node, PositionType,
+        UiRect, Val,
+    },
+    utils::{default, HashMap},
+};
+
+use super::{
+    common::{GraphChanges, NodeColor, NodeLink, NodeMetadata, NodePhysics, NodeRadius},
+    groups::MetaNode,
+    hover::HoverHighlight,
+    link_style::{ColorRamp, Scale},
+    node_shape::NodeShape,
+};
+
+/// Maximum number of categories listed in the legend.
+const MAX_LEGEND_CATEGORIES: usize = 12;
+
+/// Value of a node which drives its size or color.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeAttribute {
+    /// Number of links of the node
+    Degree,
+    /// Value of the given key in the node's [NodeMetadata]
+    Metadata(String),
+}
+
+impl NodeAttribute {
+    pub fn name(&self) -> &str {
+        match self {
+            NodeAttribute::Degree => "degree",
+            NodeAttribute::Metadata(key) => key,
+        }
+    }
+
+    fn value(&self, metadata: Option<&NodeMetadata>, degree: usize) -> Option<String> {
+        match self {
+            NodeAttribute::Degree => Some(degree.to_string()),
+            NodeAttribute::Metadata(key) => metadata?
+                .0
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value.clone()),
+        }
+    }
+}
+
+/// Maps a numeric attribute to the node's radius.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SizeEncoding {
+    pub attribute: NodeAttribute,
+    pub scale: Scale,
+    /// Smallest and largest radius in px
+    pub radius: (f32, f32),
+}
+
+/// Maps an attribute to the node's color.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorEncoding {
+    /// Every distinct value gets a color of the palette (repeating if there are
+    /// more values than colors)
+    Categorical {
+        attribute: NodeAttribute,
+        palette: Vec<Color>,
+    },
+    /// Numeric values are mapped onto the color ramp
+    Continuous {
+        attribute: NodeAttribute,
+        scale: Scale,
+        color_ramp: ColorRamp,
+    },
+}
+
+/// How nodes are styled by their data. Nodes keep their radius/color if the
+/// respective encoding is `None` or they lack the attribute.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct NodeEncodings {
+    pub size: Option<SizeEncoding>,
+    pub color: Option<ColorEncoding>,
+}
+
+/// Marker component for the legend overlay.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct NodeLegend;
+
+/// Plugin for styling nodes by their data (see [NodeEncodings]). Styles are
+/// recomputed whenever an attribute, a link or the encodings change. A legend
+/// in the bottom right corner explains the encodings.
+#[derive(Default)]
+pub struct NodeStylePlugin {
+    pub encodings: NodeEncodings,
+}
+
+impl Plugin for NodeStylePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.encodings.clone())
+            .add_systems(Startup, setup_legend)
+            .add_systems(Update, encode_node_styles);
+    }
+}
+
+/// A categorical palette with well distinguishable colors.
+pub fn default_palette() -> Vec<Color> {
+    [
+        (0.12, 0.47, 0.71),
+        (1.0, 0.5, 0.05),
+        (0.17, 0.63, 0.17),
+        (0.84, 0.15, 0.16),
+        (0.58, 0.4, 0.74),
+        (0.55, 0.34, 0.29),
+        (0.89, 0.47, 0.76),
+        (0.5, 0.5, 0.5),
+        (0.74, 0.74, 0.13),
+        (0.09, 0.75, 0.81),
+    ]
+    .into_iter()
+    .map(|(r, g, b)| Color::srgb(r, g, b))
+    .collect()
+}
+
+/// Assign palette colors to the distinct values, in sorted order.
+pub fn categorical_colors<'a>(
+    values: impl IntoIterator<Item = &'a str>,
+    palette: &[Color],
+) -> Vec<(String, Color)> {
+    let mut values = values.into_iter().collect::<Vec<_>>();
+    values.sort_unstable();
+    values.dedup();
+    values
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let color = palette.get(i % palette.len().max(1)).copied();
+            (value.to_string(), color.unwrap_or(Color::WHITE))
+        })
+        .collect()
+}
+
+/// Smallest and largest of the values.
+fn domain(values: impl IntoIterator<Item = f32>) -> (f32, f32) {
+    values
+        .into_iter()
+        .fold((f32::MAX, f32::MIN), |(min, max), value| {
+            (min.min(value), max.max(value))
+        })
+}
+
+pub fn setup_legend(mut commands: Commands) {
+    commands.spawn((
+        NodeLegend,
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(8.0),
+            right: Val::Px(8.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            flex_direction: FlexDirection::Column,
+            display: Display::None,
+            ..default()
+        },
+        BackgroundColor(Color::BLACK.with_alpha(0.6)),
+    ));
+}
+
+/// Changes of the graph which require the [NodeEncodings] to be applied
+/// again.
+#[derive(SystemParam)]
+pub struct NodeStyleChanges<'w, 's> {
+    graph_changes: GraphChanges<'w, 's>,
+    changed_metadata_q: Query<'w, 's, (), (With<NodePhysics>, Changed<NodeMetadata>)>,
+}
+
+impl NodeStyleChanges<'_, '_> {
+    pub fn any(&mut self) -> bool {
+        self.graph_changes.any() || !self.changed_metadata_q.is_empty()
+    }
+}
+
+/// Apply the [NodeEncodings] to all nodes and rebuild the legend. Meta-nodes
+/// keep their own style, see [MetaNode]. Nodes dimmed by the hover highlight
+/// keep their current alpha, the new alpha is restored once the highlight is
+/// cleared.
+pub fn encode_node_styles(
+    mut nodes_q: Query<
+        (
+            Entity,
+            Option<&NodeMetadata>,
+            Option<&NodeRadius>,
+            Option<&NodeShape>,
+            Option<&Mesh2d>,
+            Option<&mut NodeColor>,
+            Option<&mut HoverHighlight>,
+        ),
+        (With<NodePhysics>, Without<MetaNode>),
+    >,
+    mut changes: NodeStyleChanges,
+    links_q: Query<&NodeLink>,
+    encodings: Res<NodeEncodings>,
+    legend: Single<(Entity, &mut Node), With<NodeLegend>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut commands: Commands,
+) {
+    if !changes.any() && !encodings.is_changed() {
+        return;
+    }
+
+    let mut degrees = HashMap::<Entity, usize>::default();
+    for link in &links_q {
+        *degrees.entry(link.source).or_default() += 1;
+        *degrees.entry(link.target).or_default() += 1;
+    }
+    let values_of = |attribute: &NodeAttribute| {
+        nodes_q
+            .iter()
+            .filter_map(|(entity, metadata, ..)| {
+                let degree = degrees.get(&entity).copied().unwrap_or_default();
+                Some((entity, attribute.value(metadata, degree)?))
+            })
+            .collect::<HashMap<_, _>>()
+    };
+    let numbers_of = |attribute: &NodeAttribute| {
+        values_of(attribute)
+            .into_iter()
+            .filter_map(|(entity, value)| Some((entity, value.parse::<f32>().ok()?)))
+            .collect::<HashMap<_, _>>()
+    };
+
+    // Entries of the legend: color (if any) and text
+    let mut legend_entries = Vec::<(Option<Color>, String)>::new();
+
+    if let Some(SizeEncoding {
+        attribute,
+        scale,
+        radius,
+    }) = &encodings.size
+    {
+        let numbers = numbers_of(attribute);
+        let (min, max) = domain(numbers.values().copied());
+        for (entity, _, node_radius, shape, mesh, ..) in &nodes_q {
+            let Some(&number) = numbers.get(&entity) else {
+                continue;
+            };
+            let t = scale.normalize(number, min, max);
+            let new_radius = NodeRadius(radius.0 + t * (radius.1 - radius.0));
+            if node_radius == Some(&new_radius) {
+                continue;
+            }
+            commands.entity(entity).insert(new_radius);
+            if let Some(mesh) = mesh.and_then(|mesh| meshes.get_mut(&mesh.0)) {
+                *mesh = shape.cloned().unwrap_or_default().mesh(new_radius.0);
+            }
+        }
+        if !numbers.is_empty() {
+            legend_entries.push((
+                None,
+                format!("size: {} ({} - {})", attribute.name(), min, max),
+            ));
+        }
+    }
+
+    let node_colors = match &encodings.color {
+        Some(ColorEncoding::Categorical { attribute, palette }) => {
+            let values = values_of(attribute);
+            let categories = categorical_colors(values.values().map(String::as_str), palette);
+            legend_entries.push((None, format!("color: {}", attribute.name())));
+            legend_entries.extend(
+                categories
+                    .iter()
+                    .take(MAX_LEGEND_CATEGORIES)
+                    .map(|(value, color)| (Some(*color), value.clone())),
+            );
+            if categories.len() > MAX_LEGEND_CATEGORIES {
+                legend_entries.push((None, "...".to_string()));
+            }
+            let categories = categories.into_iter().collect::<HashMap<_, _>>();
+            values
+                .into_iter()
+                .filter_map(|(entity, value)| Some((entity, *categories.get(&value)?)))
+                .collect()
+        }
+        Some(ColorEncoding::Continuous {
+            attribute,
+            scale,
+            color_ramp,
+        }) => {
+            let numbers = numbers_of(attribute);
+            let (min, max) = domain(numbers.values().copied());
+            if !numbers.is_empty() {
+                legend_entries.push((None, format!("color: {}", attribute.name())));
+                legend_entries.push((Some(color_ramp.sample(0.0)), min.to_string()));
+                legend_entries.push((Some(color_ramp.sample(1.0)), max.to_string()));
+            }
+            numbers
+                .into_iter()
+                .map(|(entity, number)| {
+                    (entity, color_ramp.sample(scale.normalize(number, min, max)))
+                })
+                .collect()
+        }
+        None => HashMap::default(),
+    };
+    for (entity, _, _, _, _, node_color, highlight) in &mut nodes_q {
+        let (Some(color), Some(mut node_color)) = (node_colors.get(&entity), node_color) else {
+            continue;
+        };
+        match highlight {
+            Some(mut highlight) => {
+                node_color.set_if_neq(NodeColor(color.with_alpha(node_color.0.alpha())));
+                highlight.original_alpha = color.alpha();
+            }
+            None => {
+                node_color.set_if_neq(NodeColor(*color));
+            }
+        }
+    }
+
+    let (legend, mut legend_node) = legend.into_inner();
+    legend_node.display = if legend_entries.is_empty() {
+        Display::None
+    } else {
+        Display::Flex
+    };
+    commands
+        .entity(legend)
+        .despawn_descendants()
+        .with_children(|parent| {
+            for (color, text) in legend_entries {
+                parent
+                    .spawn(Node {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(6.0),
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        if let Some(color) = color {
+                            row.spawn((
+                                Node {
+                                    width: Val::Px(10.0),
+                                    height: Val::Px(10.0),
+                                    ..default()
+                                },
+                                BackgroundColor(color),
+                            ));
+                        }
+                        row.spawn((
+                            Text::new(text),
+                            TextFont {
+                                font_size: 12.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                        ));
+                    });
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::categorical_colors;
+    use bevy::color::Color;
+
+    #[test]
+    fn test_categorical_colors() {
+        let palette = [Color::BLACK, Color::WHITE];
+        let colors = categorical_colors(["b", "a", "c", "a"], &palette);
+        assert_eq!(
+            colors,
+            vec![
+                ("a".to_string(), Color::BLACK),
+                ("b".to_string(), Color::WHITE),
+                ("c".to_string(), Color::BLACK),
+            ]
+        );
+        assert_eq!(
+            categorical_colors(["a"], &[]),
+            vec![("a".to_string(), Color::WHITE)]
+        );
+    }
+}
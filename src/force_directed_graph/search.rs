@@ -0,0 +1,432 @@
+use std::fmt::Write as _;
+
+use bevy::{
+    app::{App, Plugin, PreUpdate, Startup, Update},
+    asset::{Assets, Handle},
+    color::{Alpha as _, Color},
+    ecs::{
+        change_detection::DetectChanges as _,
+        component::Component,
+        entity::Entity,
+        event::{Event, EventReader, EventWriter},
+        query::{Added, With, Without},
+        removal_detection::RemovedComponents,
+        schedule::IntoSystemConfigs as _,
+        system::{Commands, Query, Res, ResMut, Resource, Single},
+    },
+    hierarchy::{BuildChildren as _, ChildBuild as _, Children, DespawnRecursiveExt as _},
+    input::{
+        keyboard::{Key, KeyCode, KeyboardInput},
+        ButtonInput, InputSystem,
+    },
+    math::{primitives::Annulus, Vec3},
+    picking::PickingBehavior,
+    render::{
+        camera::{Camera, OrthographicProjection},
+        mesh::{Mesh, Mesh2d},
+    },
+    sprite::{ColorMaterial, MeshMaterial2d},
+    text::{TextColor, TextFont},
+    transform::components::Transform,
+    ui::{widget::Text, BackgroundColor, Display, Node, PositionType, UiRect, Val},
+    utils::default,
+};
+
+use super::{
+    common::{NodeId, NodeLabel, NodePhysics, NodeRadius, Pinned},
+    tween::{CameraTween, Tween, TweenSettings},
+};
+
+/// Maximum number of matches listed in the search box.
+const MAX_LISTED_MATCHES: usize = 8;
+
+/// Marker component for nodes matching the current search query.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct SearchMatch;
+
+/// Marker component for the outline which highlights a matching node. It is
+/// spawned as child of the node.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct SearchMatchOutline;
+
+/// Marker component for the search box.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct SearchBox;
+
+/// Marker component for the text within the search box.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct SearchBoxText;
+
+#[derive(Resource, Debug, Clone)]
+pub struct SearchAssets {
+    /// Ring around a node of radius 1.0, scaled to the actual radius
+    pub outline_mesh: Handle<Mesh>,
+    pub outline_material: Handle<ColorMaterial>,
+}
+
+/// State of the search box.
+#[derive(Resource, Debug, Default)]
+pub struct SearchState {
+    /// If true, the search box is open and captures keyboard input
+    pub active: bool,
+    pub query: String,
+    /// Nodes matching `query`, best match first
+    pub matches: Vec<Entity>,
+    /// Index into `matches` of the node which was focused last
+    pub current: Option<usize>,
+}
+
+/// Camera zoom when focusing a node, see [FocusNode].
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct FocusSettings {
+    /// Scale of the orthographic projection, i.e. less than 1.0 zooms in
+    pub zoom: f32,
+}
+
+/// Event to center the camera on a node. If `zoom` is true, the camera zooms
+/// in (see [SearchPlugin::zoom]). If `pin` is true, the node is pinned at its
+/// current position.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct FocusNode {
+    pub entity: Entity,
+    pub zoom: bool,
+    pub pin: bool,
+}
+
+/// Plugin for searching nodes by label or id:
+///
+/// * `/` - open the search box, then type to search
+/// * `Enter` - focus the next match (see [FocusNode])
+/// * `Shift+Enter` - focus the next match and pin it
+/// * `Escape` - close the search box
+///
+/// While the search box is open, other keyboard shortcuts are disabled.
+pub struct SearchPlugin {
+    /// Scale of the orthographic projection when focusing a node
+    pub zoom: f32,
+}
+
+impl Default for SearchPlugin {
+    fn default() -> Self {
+        Self { zoom: 0.5 }
+    }
+}
+
+impl Plugin for SearchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SearchState>()
+            .insert_resource(FocusSettings { zoom: self.zoom })
+            .add_event::<FocusNode>()
+            .add_systems(Startup, setup_search)
+            // Runs before any other system reads the keyboard
+            .add_systems(PreUpdate, search_keyboard_input.after(InputSystem))
+            .add_systems(
+                Update,
+                (
+                    (update_search_matches, update_search_box).chain(),
+                    focus_node,
+                    add_search_match_outlines,
+                    remove_search_match_outlines,
+                ),
+            );
+    }
+}
+
+pub fn setup_search(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(SearchAssets {
+        // Outside of the selection outline, so both can be seen at once
+        outline_mesh: meshes.add(Annulus::new(1.55, 1.85)),
+        outline_material: materials.add(Color::srgba(1.0, 0.85, 0.0, 0.9)),
+    });
+
+    commands
+        .spawn((
+            SearchBox,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(8.0),
+                left: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                display: Display::None,
+                ..default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.6)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                SearchBoxText,
+                Text::default(),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
+
+/// How well `name` matches `query`, ignoring case. Lower is better: 0 for an
+/// exact match, 1 for a prefix, 2 for a substring. `None` if there is no match.
+pub fn match_rank(query: &str, name: &str) -> Option<u8> {
+    let query = query.to_lowercase();
+    let name = name.to_lowercase();
+    if query.is_empty() {
+        None
+    } else if name == query {
+        Some(0)
+    } else if name.starts_with(&query) {
+        Some(1)
+    } else if name.contains(&query) {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// Text input for the search box. Keyboard shortcuts of other plugins are
+/// suppressed while the search box is open by clearing the just pressed keys.
+pub fn search_keyboard_input(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut keyboard: ResMut<ButtonInput<KeyCode>>,
+    mut state: ResMut<SearchState>,
+    mut focus_events: EventWriter<FocusNode>,
+) {
+    if !state.active {
+        if keyboard.just_pressed(KeyCode::Slash) {
+            state.active = true;
+            // The slash itself is not part of the query
+            keyboard_events.clear();
+            keyboard.clear();
+        }
+        return;
+    }
+
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    for event in keyboard_events.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(characters) => {
+                state
+                    .query
+                    .extend(characters.chars().filter(|c| !c.is_control()));
+            }
+            Key::Space => state.query.push(' '),
+            Key::Backspace => {
+                state.query.pop();
+            }
+            Key::Enter if !state.matches.is_empty() => {
+                let current = state
+                    .current
+                    .map_or(0, |current| (current + 1) % state.matches.len());
+                state.current = Some(current);
+                focus_events.send(FocusNode {
+                    entity: state.matches[current],
+                    zoom: true,
+                    pin: shift,
+                });
+            }
+            Key::Escape => {
+                state.active = false;
+                state.query.clear();
+                break;
+            }
+            _ => {}
+        }
+    }
+    keyboard.clear();
+}
+
+/// Find the nodes matching the query whenever it changes and mark them with
+/// [SearchMatch].
+pub fn update_search_matches(
+    mut state: ResMut<SearchState>,
+    nodes_q: Query<(Entity, Option<&NodeLabel>, Option<&NodeId>), With<NodePhysics>>,
+    matches_q: Query<Entity, With<SearchMatch>>,
+    mut commands: Commands,
+) {
+    if !state.is_changed() {
+        return;
+    }
+
+    let mut matches = nodes_q
+        .iter()
+        .filter_map(|(entity, label, id)| {
+            let label = label.map(|label| label.0.as_str());
+            let id = id.map(|id| id.0.as_str());
+            let rank = [label, id]
+                .into_iter()
+                .flatten()
+                .filter_map(|name| match_rank(&state.query, name))
+                .min()?;
+            Some((rank, label.or(id).unwrap_or_default().to_string(), entity))
+        })
+        .collect::<Vec<_>>();
+    matches.sort();
+    let matches = matches
+        .into_iter()
+        .map(|(_, _, entity)| entity)
+        .collect::<Vec<_>>();
+
+    if matches == state.matches {
+        return;
+    }
+    for entity in &matches_q {
+        if !matches.contains(&entity) {
+            commands.entity(entity).remove::<SearchMatch>();
+        }
+    }
+    for &entity in &matches {
+        if !matches_q.contains(entity) {
+            commands.entity(entity).insert(SearchMatch);
+        }
+    }
+    state.matches = matches;
+    state.current = None;
+}
+
+pub fn update_search_box(
+    state: Res<SearchState>,
+    nodes_q: Query<(Option<&NodeLabel>, Option<&NodeId>)>,
+    mut search_box: Single<&mut Node, With<SearchBox>>,
+    mut text: Single<&mut Text, With<SearchBoxText>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+
+    search_box.display = if state.active {
+        Display::Flex
+    } else {
+        Display::None
+    };
+
+    // Writing into a String cannot fail
+    let mut content = format!("Search: {}_", state.query);
+    if !state.query.is_empty() {
+        let _ = write!(content, "\n{} matches", state.matches.len());
+    }
+    for (i, entity) in state.matches.iter().enumerate().take(MAX_LISTED_MATCHES) {
+        let name = nodes_q
+            .get(*entity)
+            .ok()
+            .and_then(|(label, id)| {
+                label
+                    .map(|label| label.0.clone())
+                    .or_else(|| id.map(|id| id.0.clone()))
+            })
+            .unwrap_or_else(|| entity.to_string());
+        let marker = if state.current == Some(i) { ">" } else { " " };
+        let _ = write!(content, "\n{} {}", marker, name);
+    }
+    if state.matches.len() > MAX_LISTED_MATCHES {
+        let _ = write!(content, "\n  ...");
+    }
+    text.0 = content;
+}
+
+/// Center the camera on a node on a [FocusNode] event. The camera
+/// is animated if [TweenSettings] are given.
+pub fn focus_node(
+    mut focus_events: EventReader<FocusNode>,
+    nodes_q: Query<&Transform, (With<NodePhysics>, Without<Camera>)>,
+    mut camera_q: Query<
+        (Entity, &mut Transform, &mut OrthographicProjection),
+        (With<Camera>, Without<NodePhysics>),
+    >,
+    settings: Res<FocusSettings>,
+    tween_settings: Option<Res<TweenSettings>>,
+    mut commands: Commands,
+) {
+    for event in focus_events.read() {
+        let Ok(node_transform) = nodes_q.get(event.entity) else {
+            continue;
+        };
+        let position = node_transform.translation.truncate();
+        if event.pin {
+            commands.entity(event.entity).insert(Pinned { position });
+        }
+
+        let Ok((camera, mut transform, mut projection)) = camera_q.get_single_mut() else {
+            continue;
+        };
+        let scale = if event.zoom {
+            settings.zoom
+        } else {
+            projection.scale
+        };
+        match tween_settings.as_deref() {
+            Some(tween_settings) if tween_settings.duration > 0.0 => {
+                commands.entity(camera).insert(CameraTween {
+                    translation: Tween::new(
+                        transform.translation.truncate(),
+                        position,
+                        tween_settings.duration,
+                        tween_settings.easing,
+                    ),
+                    start_scale: projection.scale,
+                    target_scale: scale,
+                });
+            }
+            _ => {
+                transform.translation = position.extend(transform.translation.z);
+                projection.scale = scale;
+            }
+        }
+    }
+}
+
+/// Highlight newly matching nodes with an outline.
+pub fn add_search_match_outlines(
+    matches_q: Query<(Entity, Option<&NodeRadius>), Added<SearchMatch>>,
+    assets: Res<SearchAssets>,
+    mut commands: Commands,
+) {
+    for (entity, radius) in &matches_q {
+        let radius = radius.copied().unwrap_or_default().0;
+        commands.entity(entity).with_child((
+            SearchMatchOutline,
+            Mesh2d(assets.outline_mesh.clone()),
+            MeshMaterial2d(assets.outline_material.clone()),
+            Transform::from_xyz(0.0, 0.0, 0.25).with_scale(Vec3::new(radius, radius, 1.0)),
+            // Clicking the outline should not count as clicking the node
+            PickingBehavior::IGNORE,
+        ));
+    }
+}
+
+/// Remove the outline of nodes which do not match anymore.
+pub fn remove_search_match_outlines(
+    mut removed: RemovedComponents<SearchMatch>,
+    children_q: Query<&Children>,
+    outlines_q: Query<(), With<SearchMatchOutline>>,
+    mut commands: Commands,
+) {
+    for entity in removed.read() {
+        let Ok(children) = children_q.get(entity) else {
+            continue;
+        };
+        for child in children.iter().filter(|child| outlines_q.contains(**child)) {
+            commands.entity(*child).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::match_rank;
+
+    #[test]
+    fn test_match_rank() {
+        assert_eq!(match_rank("node", "Node"), Some(0));
+        assert_eq!(match_rank("no", "Node 42"), Some(1));
+        assert_eq!(match_rank("42", "Node 42"), Some(2));
+        assert_eq!(match_rank("43", "Node 42"), None);
+        assert_eq!(match_rank("", "Node 42"), None);
+    }
+}
@@ -0,0 +1,317 @@
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    asset::{Assets, Handle},
+    color::Color,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        observer::Trigger,
+        query::{Added, Has, With},
+        removal_detection::RemovedComponents,
+        system::{Commands, Query, Res, ResMut, Resource, SystemParam},
+    },
+    hierarchy::{BuildChildren as _, Children, DespawnRecursiveExt as _},
+    input::{keyboard::KeyCode, mouse::MouseButton, ButtonInput},
+    math::{primitives::Annulus, Rect, Vec2, Vec3},
+    picking::{
+        events::{Click, Pointer},
+        focus::HoverMap,
+        pointer::{PointerButton, PointerId},
+        PickingBehavior,
+    },
+    render::{
+        camera::Camera,
+        mesh::{Mesh, Mesh2d},
+    },
+    sprite::{ColorMaterial, MeshMaterial2d},
+    transform::components::{GlobalTransform, Transform},
+    ui::{BackgroundColor, BorderColor, Interaction, Node, PositionType, UiRect, Val},
+    window::{PrimaryWindow, Window},
+};
+
+use super::common::{MouseLocked, NodePhysics, NodeRadius};
+
+/// Marker component for selected nodes. Dragging any selected node moves the
+/// whole selection (see [crate::force_directed_graph::mouse]).
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Selected;
+
+/// Marker component for the outline which highlights a selected node. It is
+/// spawned as child of the node.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct SelectionOutline;
+
+/// Marker component for the rubber-band rectangle of a box selection.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct SelectionBox;
+
+#[derive(Resource, Debug, Clone)]
+pub struct SelectionAssets {
+    /// Ring around a node of radius 1.0, scaled to the actual radius
+    pub outline_mesh: Handle<Mesh>,
+    pub outline_material: Handle<ColorMaterial>,
+}
+
+/// State of a box selection. The rectangle is spanned from where the mouse
+/// button was pressed on the background to the current cursor position. While
+/// the cursor is outside of the window, the rectangle keeps its last extent.
+#[derive(Resource, Debug, Default)]
+pub struct BoxSelection {
+    /// Cursor position in window coordinates where the box selection started
+    pub start: Option<Vec2>,
+    /// Last cursor position within the window in window coordinates
+    pub end: Vec2,
+}
+
+/// Plugin for selecting nodes:
+///
+/// * click - select a single node (see [select_on_click])
+/// * shift-click - add/remove a node to/from the selection
+/// * drag on the background - select all nodes within the rectangle (with
+///   shift: add them to the selection)
+/// * `Escape` - clear the selection
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BoxSelection>()
+            .add_systems(Startup, setup_selection_assets)
+            .add_systems(
+                Update,
+                (
+                    box_select,
+                    clear_selection_on_escape,
+                    add_selection_outlines,
+                    remove_selection_outlines,
+                ),
+            );
+    }
+}
+
+pub fn setup_selection_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(SelectionAssets {
+        outline_mesh: meshes.add(Annulus::new(1.15, 1.45)),
+        outline_material: materials.add(Color::srgba(1.0, 1.0, 1.0, 0.9)),
+    });
+}
+
+fn is_shift_pressed(keyboard: &ButtonInput<KeyCode>) -> bool {
+    keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+}
+
+/// Observer for click events on nodes. Selects the node only, or with shift
+/// toggles whether the node is selected. Clicks which end a drag-and-drop and
+/// clicks with other buttons than the primary one are ignored.
+pub fn select_on_click(
+    trigger: Trigger<Pointer<Click>>,
+    nodes_q: Query<(Has<Selected>, Has<MouseLocked>)>,
+    selected_q: Query<Entity, With<Selected>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut commands: Commands,
+) {
+    if trigger.event().button != PointerButton::Primary {
+        return;
+    }
+    let entity = trigger.entity();
+    let Ok((selected, mouse_locked)) = nodes_q.get(entity) else {
+        return;
+    };
+    // The node is locked until the drag ends, which happens after the click
+    if mouse_locked {
+        return;
+    }
+
+    if is_shift_pressed(&keyboard) {
+        if selected {
+            commands.entity(entity).remove::<Selected>();
+        } else {
+            commands.entity(entity).insert(Selected);
+        }
+    } else {
+        for other in selected_q.iter().filter(|other| *other != entity) {
+            commands.entity(other).remove::<Selected>();
+        }
+        commands.entity(entity).insert(Selected);
+    }
+}
+
+/// Input of the box selection: buttons, cursor and what is below it.
+#[derive(SystemParam)]
+pub struct BoxSelectInput<'w, 's> {
+    mouse: Res<'w, ButtonInput<MouseButton>>,
+    keyboard: Res<'w, ButtonInput<KeyCode>>,
+    hover_map: Res<'w, HoverMap>,
+    window_q: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    camera_q: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+    interactions_q: Query<'w, 's, &'static Interaction>,
+}
+
+/// Rubber-band selection on the background. The selection ends when the mouse
+/// button is released, even if the cursor left the window.
+pub fn box_select(
+    mut box_selection: ResMut<BoxSelection>,
+    input: BoxSelectInput,
+    nodes_q: Query<(Entity, &Transform, Has<Selected>), With<NodePhysics>>,
+    mut box_q: Query<(Entity, &mut Node), With<SelectionBox>>,
+    mut commands: Commands,
+) {
+    let cursor = input
+        .window_q
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position());
+
+    if let Some(cursor) = cursor.filter(|_| input.mouse.just_pressed(MouseButton::Left)) {
+        let on_node = input
+            .hover_map
+            .get(&PointerId::Mouse)
+            .is_some_and(|hovered| hovered.keys().any(|entity| nodes_q.contains(*entity)));
+        // E.g. a button of a menu
+        let on_ui = input
+            .interactions_q
+            .iter()
+            .any(|interaction| *interaction != Interaction::None);
+        if !on_node && !on_ui {
+            box_selection.start = Some(cursor);
+            commands.spawn((
+                SelectionBox,
+                Node {
+                    position_type: PositionType::Absolute,
+                    border: UiRect::all(Val::Px(1.0)),
+                    ..Default::default()
+                },
+                BorderColor(Color::srgba(1.0, 1.0, 1.0, 0.8)),
+                BackgroundColor(Color::srgba(1.0, 1.0, 1.0, 0.1)),
+            ));
+        }
+    }
+
+    let Some(start) = box_selection.start else {
+        return;
+    };
+    if let Some(cursor) = cursor {
+        box_selection.end = cursor;
+    }
+    let rect = Rect::from_corners(start, box_selection.end);
+
+    if input.mouse.pressed(MouseButton::Left) {
+        for (_, mut node) in &mut box_q {
+            node.left = Val::Px(rect.min.x);
+            node.top = Val::Px(rect.min.y);
+            node.width = Val::Px(rect.width());
+            node.height = Val::Px(rect.height());
+        }
+        return;
+    }
+
+    // Mouse button released, possibly outside of the window
+    box_selection.start = None;
+    for (entity, _) in &box_q {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let Ok((camera, camera_transform)) = input.camera_q.get_single() else {
+        return;
+    };
+    let (Ok(a), Ok(b)) = (
+        camera.viewport_to_world_2d(camera_transform, rect.min),
+        camera.viewport_to_world_2d(camera_transform, rect.max),
+    ) else {
+        return;
+    };
+    let world_rect = Rect::from_corners(a, b);
+
+    let additive = is_shift_pressed(&input.keyboard);
+    for (entity, transform, selected) in &nodes_q {
+        let inside = world_rect.contains(transform.translation.truncate());
+        if inside && !selected {
+            commands.entity(entity).insert(Selected);
+        } else if !inside && selected && !additive {
+            commands.entity(entity).remove::<Selected>();
+        }
+    }
+}
+
+pub fn clear_selection_on_escape(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    selected_q: Query<Entity, With<Selected>>,
+    mut commands: Commands,
+) {
+    if keyboard.just_pressed(KeyCode::Escape) {
+        for entity in &selected_q {
+            commands.entity(entity).remove::<Selected>();
+        }
+    }
+}
+
+/// Highlight newly selected nodes with an outline.
+pub fn add_selection_outlines(
+    selected_q: Query<(Entity, Option<&NodeRadius>), Added<Selected>>,
+    assets: Res<SelectionAssets>,
+    mut commands: Commands,
+) {
+    for (entity, radius) in &selected_q {
+        let radius = radius.copied().unwrap_or_default().0;
+        commands.entity(entity).with_child((
+            SelectionOutline,
+            Mesh2d(assets.outline_mesh.clone()),
+            MeshMaterial2d(assets.outline_material.clone()),
+            Transform::from_xyz(0.0, 0.0, 0.25).with_scale(Vec3::new(radius, radius, 1.0)),
+            // Clicking the outline should not count as clicking the node
+            PickingBehavior::IGNORE,
+        ));
+    }
+}
+
+/// Remove the outline of nodes which are not selected anymore.
+pub fn remove_selection_outlines(
+    mut deselected: RemovedComponents<Selected>,
+    children_q: Query<&Children>,
+    outlines_q: Query<(), With<SelectionOutline>>,
+    mut commands: Commands,
+) {
+    for entity in deselected.read() {
+        let Ok(children) = children_q.get(entity) else {
+            continue;
+        };
+        for child in children.iter().filter(|child| outlines_q.contains(**child)) {
+            commands.entity(*child).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{box_select, BoxSelection, SelectionBox};
+    use bevy::{
+        app::{App, Update},
+        input::{keyboard::KeyCode, mouse::MouseButton, ButtonInput},
+        math::Vec2,
+        picking::focus::HoverMap,
+        ui::Node,
+    };
+
+    #[test]
+    fn test_release_outside_of_window() {
+        let mut app = App::new();
+        // Without a window there is no cursor position, like outside of it
+        app.init_resource::<ButtonInput<MouseButton>>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<HoverMap>()
+            .insert_resource(BoxSelection {
+                start: Some(Vec2::ZERO),
+                end: Vec2::new(10.0, 10.0),
+            })
+            .add_systems(Update, box_select);
+        let selection_box = app.world_mut().spawn((SelectionBox, Node::default())).id();
+
+        app.update();
+
+        assert_eq!(app.world().resource::<BoxSelection>().start, None);
+        assert!(app.world().get_entity(selection_box).is_err());
+    }
+}
@@ -0,0 +1,288 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        entity::Entity,
+        event::{Event, EventReader, EventWriter},
+        system::{Commands, Query, Res, ResMut},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    log::{error, info},
+    math::{Vec2, Vec3},
+    transform::components::Transform,
+    utils::HashMap,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    common::{NodeId, NodePhysics, Pinned},
+    forces::fruchterman_reingold::FruchtermanReingoldRes,
+    tween::{self, TweenSettings},
+};
+
+/// Saved state of a single node, identified by its [NodeId].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeSnapshot {
+    pub id: String,
+    pub position: Vec3,
+    pub previous_position: Vec2,
+    pub pinned: bool,
+}
+
+/// Saved layout of all nodes with a [NodeId]. Stored as RON.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LayoutSnapshot {
+    pub nodes: Vec<NodeSnapshot>,
+}
+
+impl LayoutSnapshot {
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let ron = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(io::Error::other)?;
+        fs::write(path, ron)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let ron = fs::read_to_string(path)?;
+        ron::from_str(&ron).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Event to save the layout of all nodes to a file.
+#[derive(Event, Debug, Clone)]
+pub struct SaveSnapshot(pub PathBuf);
+
+/// Event to restore the layout of all nodes from a file. Nodes are mapped by
+/// their [NodeId], nodes which are not part of the snapshot are not moved.
+#[derive(Event, Debug, Clone)]
+pub struct LoadSnapshot(pub PathBuf);
+
+/// Plugin for saving and restoring layouts. The layout can be saved/restored
+/// via the [SaveSnapshot]/[LoadSnapshot] events or via keyboard:
+///
+/// * `Ctrl+S` - save the layout to `path`
+/// * `Ctrl+L` - restore the layout from `path`
+pub struct SnapshotPlugin {
+    pub path: PathBuf,
+}
+
+impl Default for SnapshotPlugin {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("layout.ron"),
+        }
+    }
+}
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveSnapshot>()
+            .add_event::<LoadSnapshot>()
+            .add_systems(
+                Update,
+                (
+                    snapshot_keyboard_input(self.path.clone()),
+                    save_snapshot,
+                    load_snapshot,
+                ),
+            );
+    }
+}
+
+/// Send [SaveSnapshot]/[LoadSnapshot] events on key presses. See
+/// [SnapshotPlugin].
+pub fn snapshot_keyboard_input(
+    path: PathBuf,
+) -> impl Fn(Res<'_, ButtonInput<KeyCode>>, EventWriter<'_, SaveSnapshot>, EventWriter<'_, LoadSnapshot>)
+{
+    move |keyboard: Res<ButtonInput<KeyCode>>,
+          mut save_events: EventWriter<SaveSnapshot>,
+          mut load_events: EventWriter<LoadSnapshot>| {
+        if !keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+            return;
+        }
+        if keyboard.just_pressed(KeyCode::KeyS) {
+            save_events.send(SaveSnapshot(path.clone()));
+        }
+        if keyboard.just_pressed(KeyCode::KeyL) {
+            load_events.send(LoadSnapshot(path.clone()));
+        }
+    }
+}
+
+/// Save the layout of all nodes with a [NodeId] on a [SaveSnapshot] event.
+pub fn save_snapshot(
+    mut save_events: EventReader<SaveSnapshot>,
+    nodes_q: Query<(&NodeId, &Transform, &NodePhysics, Option<&Pinned>)>,
+) {
+    for SaveSnapshot(path) in save_events.read() {
+        let snapshot = LayoutSnapshot {
+            nodes: nodes_q
+                .iter()
+                .map(|(id, transform, node_physics, pinned)| NodeSnapshot {
+                    id: id.0.clone(),
+                    position: transform.translation,
+                    previous_position: node_physics.previous_position,
+                    pinned: pinned.is_some(),
+                })
+                .collect(),
+        };
+
+        match snapshot.save(path) {
+            Ok(()) => info!("Saved layout to {}", path.display()),
+            Err(e) => error!("Cannot save layout to {}: {}", path.display(), e),
+        }
+    }
+}
+
+/// Restore the layout of all nodes with a [NodeId] on a [LoadSnapshot] event.
+/// If [TweenSettings] are present, nodes are animated to their restored
+/// positions. Their velocity is restored once they got there. The
+/// Fruchterman–Reingold temperature is reset, so the restored layout can
+/// still be refined.
+pub fn load_snapshot(
+    mut load_events: EventReader<LoadSnapshot>,
+    mut nodes_q: Query<(Entity, &NodeId, &mut Transform, &mut NodePhysics)>,
+    tween_settings: Option<Res<TweenSettings>>,
+    mut fruchterman_reingold: Option<ResMut<FruchtermanReingoldRes>>,
+    mut commands: Commands,
+) {
+    for LoadSnapshot(path) in load_events.read() {
+        let snapshot = match LayoutSnapshot::load(path) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                error!("Cannot load layout from {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        let snapshots = snapshot
+            .nodes
+            .iter()
+            .map(|node| (node.id.as_str(), node))
+            .collect::<HashMap<_, _>>();
+
+        for (entity, id, mut transform, mut node_physics) in &mut nodes_q {
+            let Some(node) = snapshots.get(id.0.as_str()) else {
+                continue;
+            };
+
+            let position = node.position.truncate();
+            if node.pinned {
+                commands.entity(entity).insert(Pinned { position });
+            } else {
+                commands.entity(entity).remove::<Pinned>();
+            }
+
+            transform.translation.z = node.position.z;
+            tween::move_node_with_velocity(
+                &mut commands,
+                entity,
+                &mut transform,
+                &mut node_physics,
+                position,
+                position - node.previous_position,
+                tween_settings.as_deref(),
+            );
+        }
+        if let Some(fruchterman_reingold) = fruchterman_reingold.as_deref_mut() {
+            fruchterman_reingold.reheat();
+        }
+        info!("Restored layout from {}", path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::{app::PostUpdate, math::curve::EaseFunction, time::Time};
+    use std::time::Duration;
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let snapshot = LayoutSnapshot {
+            nodes: vec![
+                NodeSnapshot {
+                    id: "a".to_string(),
+                    position: Vec3::new(1.0, 2.0, 0.5),
+                    previous_position: Vec2::new(0.5, 1.5),
+                    pinned: false,
+                },
+                NodeSnapshot {
+                    id: "b".to_string(),
+                    position: Vec3::new(-3.0, 4.0, 0.25),
+                    previous_position: Vec2::new(-3.0, 4.0),
+                    pinned: true,
+                },
+            ],
+        };
+        // Unique per process, so concurrent test runs don't share the file
+        let path = std::env::temp_dir().join(format!(
+            "bevy_force_directed_graph_test_snapshot_{}.ron",
+            std::process::id()
+        ));
+
+        snapshot.save(&path).unwrap();
+        let loaded = LayoutSnapshot::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, snapshot);
+    }
+
+    #[test]
+    fn test_load_snapshot_velocity() {
+        let snapshot = LayoutSnapshot {
+            nodes: vec![NodeSnapshot {
+                id: "a".to_string(),
+                position: Vec3::new(10.0, 0.0, 0.5),
+                previous_position: Vec2::new(8.0, 0.0),
+                pinned: false,
+            }],
+        };
+        let path = std::env::temp_dir().join(format!(
+            "bevy_force_directed_graph_test_velocity_{}.ron",
+            std::process::id()
+        ));
+        snapshot.save(&path).unwrap();
+
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .insert_resource(TweenSettings {
+                duration: 0.5,
+                easing: EaseFunction::Linear,
+            })
+            .add_event::<LoadSnapshot>()
+            .add_systems(Update, load_snapshot)
+            .add_systems(PostUpdate, tween::animate_tweens);
+        let node = app
+            .world_mut()
+            .spawn((
+                NodeId("a".to_string()),
+                Transform::default(),
+                NodePhysics::from_transform(Transform::default()),
+            ))
+            .id();
+
+        // The node is animated to its position first, without velocity
+        app.world_mut().send_event(LoadSnapshot(path.clone()));
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(0.25));
+        app.update();
+        fs::remove_file(&path).unwrap();
+        let node_physics = app.world().get::<NodePhysics>(node).unwrap();
+        let transform = app.world().get::<Transform>(node).unwrap();
+        assert_eq!(transform.translation, Vec3::new(5.0, 0.0, 0.5));
+        assert_eq!(node_physics.previous_position, Vec2::new(5.0, 0.0));
+
+        // Then its velocity is restored
+        app.update();
+        let node_physics = app.world().get::<NodePhysics>(node).unwrap();
+        let transform = app.world().get::<Transform>(node).unwrap();
+        assert_eq!(transform.translation, Vec3::new(10.0, 0.0, 0.5));
+        assert_eq!(node_physics.previous_position, Vec2::new(8.0, 0.0));
+    }
+}
@@ -0,0 +1,465 @@
+use std::fmt::Write as _;
+
+use bevy::{
+    app::{App, Plugin, PreUpdate, Startup, Update},
+    color::{Alpha as _, Color},
+    ecs::{
+        change_detection::DetectChanges as _,
+        component::Component,
+        entity::Entity,
+        event::{Event, EventWriter},
+        query::With,
+        schedule::{IntoSystemConfigs as _, IntoSystemSetConfigs as _},
+        system::{Commands, Query, Res, ResMut, Resource, Single},
+    },
+    hierarchy::{BuildChildren as _, ChildBuild as _},
+    input::{keyboard::KeyCode, ButtonInput},
+    math::Vec2,
+    text::{TextColor, TextFont},
+    transform::components::Transform,
+    ui::{widget::Text, BackgroundColor, Display, JustifyContent, Node, PositionType, UiRect, Val},
+    utils::default,
+};
+
+use super::{
+    common::{GraphChanges, NodeLink, NodePhysics},
+    control::SimulationControl,
+    verlet::VerletRes,
+    ForceModel, SimulationSet,
+};
+
+/// Metrics of the last simulation tick, to decide whether the layout has
+/// converged.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct SimulationStats {
+    /// Number of ticks so far
+    pub tick: u64,
+    /// Sum of `v²/2` over all nodes (mass 1), in px²/s²
+    pub kinetic_energy: f32,
+    /// Largest distance a node moved within the tick, in px
+    pub max_displacement: f32,
+    /// Sum of the squared relative deviations of the links' lengths from their
+    /// target distances
+    pub link_stress: f32,
+    /// Number of pairs of crossing links. `None` if not computed, see
+    /// [SimulationStatsPlugin].
+    pub edge_crossings: Option<usize>,
+}
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct SimulationStatsSettings {
+    pub edge_crossings: bool,
+    /// Kinetic energy below which the simulation is considered calm
+    pub energy_threshold: f32,
+    /// Number of consecutive calm ticks until the simulation is settled
+    pub settle_ticks: u32,
+    /// Whether to pause the simulation while it is settled
+    pub pause_when_settled: bool,
+}
+
+/// Whether the layout is done, see [SimulationSettled].
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SettleState {
+    pub settled: bool,
+    /// Number of consecutive calm ticks
+    pub calm_ticks: u32,
+}
+
+/// Event sent when the kinetic energy stayed below the threshold for a number
+/// of ticks, see [SimulationStatsPlugin].
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulationSettled;
+
+/// Event sent when a settled simulation starts moving again: the kinetic
+/// energy rose (e.g. a node was dragged) or the graph changed.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulationWoke;
+
+/// Marker component for the stats overlay.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct StatsOverlay;
+
+/// Marker component for the text within the stats overlay.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct StatsText;
+
+/// Plugin which computes the [SimulationStats] every tick and shows them in an
+/// overlay at the top (toggled with `F3`). [SimulationSettled] and
+/// [SimulationWoke] events are sent when the layout is done or moving again.
+///
+/// * `edge_crossings` - whether to count crossing links, off by default. This
+///   checks all pairs of links, so it is slow for large graphs. They are only
+///   counted while the overlay is visible (or if there is none, e.g. headless).
+/// * `energy_threshold` - kinetic energy below which the simulation is calm
+/// * `settle_ticks` - number of consecutive calm ticks until it is settled
+/// * `pause_when_settled` - pause the integration and all forces (see
+///   [SimulationSet]) while settled. The simulation resumes when a node is
+///   dragged or the graph changes.
+pub struct SimulationStatsPlugin {
+    pub edge_crossings: bool,
+    pub energy_threshold: f32,
+    pub settle_ticks: u32,
+    pub pause_when_settled: bool,
+}
+
+impl Default for SimulationStatsPlugin {
+    fn default() -> Self {
+        Self {
+            edge_crossings: false,
+            energy_threshold: 100.0,
+            settle_ticks: 60,
+            pause_when_settled: false,
+        }
+    }
+}
+
+impl Plugin for SimulationStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SimulationStatsSettings {
+            edge_crossings: self.edge_crossings,
+            energy_threshold: self.energy_threshold,
+            settle_ticks: self.settle_ticks,
+            pause_when_settled: self.pause_when_settled,
+        })
+        .init_resource::<SimulationStats>()
+        .init_resource::<SettleState>()
+        .add_event::<SimulationSettled>()
+        .add_event::<SimulationWoke>()
+        .configure_sets(
+            PreUpdate,
+            SimulationSet::Integration.run_if(simulation_awake),
+        )
+        .configure_sets(
+            Update,
+            (SimulationSet::Forces, SimulationSet::Constraints).run_if(simulation_awake),
+        )
+        .add_systems(Startup, setup_stats_overlay)
+        .add_systems(
+            Update,
+            (
+                update_simulation_stats
+                    .after(SimulationSet::Constraints)
+                    .run_if(simulation_integrating),
+                update_settle_state,
+                toggle_stats_overlay,
+                update_stats_overlay,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Run condition: false while the simulation is settled and paused, see
+/// [SimulationStatsPlugin].
+pub fn simulation_awake(state: Res<SettleState>, settings: Res<SimulationStatsSettings>) -> bool {
+    !(state.settled && settings.pause_when_settled)
+}
+
+/// Run condition: false while the simulation is paused (see
+/// [SimulationControl]) or a static layout is shown (see
+/// [ForceModel::Static]). Nodes don't move then, so there are no stats to
+/// compute.
+pub fn simulation_integrating(
+    control: Option<Res<SimulationControl>>,
+    force_model: Option<Res<ForceModel>>,
+) -> bool {
+    control.is_none_or(|control| control.running())
+        && force_model.is_none_or(|force_model| *force_model != ForceModel::Static)
+}
+
+pub fn setup_stats_overlay(mut commands: Commands) {
+    // Full-width row to center the overlay horizontally
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    StatsOverlay,
+                    Node {
+                        padding: UiRect::all(Val::Px(8.0)),
+                        display: Display::None,
+                        ..default()
+                    },
+                    BackgroundColor(Color::BLACK.with_alpha(0.6)),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        StatsText,
+                        Text::default(),
+                        TextFont {
+                            font_size: 12.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+                });
+        });
+}
+
+/// Whether the segments `a` and `b` properly cross each other (touching or
+/// collinear segments don't count).
+pub fn segments_cross(a: (Vec2, Vec2), b: (Vec2, Vec2)) -> bool {
+    let side = |p: Vec2, q: Vec2, r: Vec2| (q - p).perp_dot(r - p);
+    let d1 = side(b.0, b.1, a.0);
+    let d2 = side(b.0, b.1, a.1);
+    let d3 = side(a.0, a.1, b.0);
+    let d4 = side(a.0, a.1, b.1);
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+}
+
+/// Number of pairs of crossing links. Links sharing a node don't count.
+pub fn count_crossings(links: &[(Entity, Entity, Vec2, Vec2)]) -> usize {
+    let mut crossings = 0;
+    for (i, &(a_source, a_target, a_start, a_end)) in links.iter().enumerate() {
+        for &(b_source, b_target, b_start, b_end) in &links[i + 1..] {
+            let share_node = [b_source, b_target].contains(&a_source)
+                || [b_source, b_target].contains(&a_target);
+            if !share_node && segments_cross((a_start, a_end), (b_start, b_end)) {
+                crossings += 1;
+            }
+        }
+    }
+    crossings
+}
+
+/// Compute the [SimulationStats] after all forces and constraints. Edge
+/// crossings are skipped while the overlay is hidden, see
+/// [SimulationStatsPlugin].
+pub fn update_simulation_stats(
+    nodes_q: Query<(&Transform, &NodePhysics)>,
+    links_q: Query<&NodeLink>,
+    overlay_q: Query<&Node, With<StatsOverlay>>,
+    verlet: Option<Res<VerletRes>>,
+    settings: Res<SimulationStatsSettings>,
+    mut stats: ResMut<SimulationStats>,
+) {
+    let delta_secs = verlet
+        .map(|verlet| verlet.delta_secs())
+        .filter(|delta| *delta > 0.0)
+        .unwrap_or(1.0);
+
+    let mut kinetic_energy = 0.0;
+    let mut max_displacement = 0.0_f32;
+    for (transform, physics) in &nodes_q {
+        let displacement = transform.translation.truncate() - physics.previous_position;
+        let velocity = displacement / delta_secs;
+        kinetic_energy += 0.5 * velocity.length_squared();
+        max_displacement = max_displacement.max(displacement.length());
+    }
+
+    let links = links_q
+        .iter()
+        .filter_map(|link| {
+            let (source, _) = nodes_q.get(link.source).ok()?;
+            let (target, _) = nodes_q.get(link.target).ok()?;
+            Some((
+                link,
+                source.translation.truncate(),
+                target.translation.truncate(),
+            ))
+        })
+        .collect::<Vec<_>>();
+    let link_stress = links
+        .iter()
+        .map(|(link, source, target)| {
+            let deviation = (source.distance(*target) - link.target_distance)
+                / link.target_distance.max(f32::EPSILON);
+            deviation * deviation
+        })
+        .sum();
+    let overlay_visible = overlay_q
+        .iter()
+        .all(|overlay| overlay.display != Display::None);
+    let edge_crossings = (settings.edge_crossings && overlay_visible).then(|| {
+        count_crossings(
+            &links
+                .iter()
+                .map(|(link, source, target)| (link.source, link.target, *source, *target))
+                .collect::<Vec<_>>(),
+        )
+    });
+
+    *stats = SimulationStats {
+        tick: stats.tick + 1,
+        kinetic_energy,
+        max_displacement,
+        link_stress,
+        edge_crossings,
+    };
+}
+
+/// Settle the simulation when it was calm for long enough, and wake it when
+/// the kinetic energy rises or nodes/links are added or removed. Only ticks
+/// which updated the [SimulationStats] count as calm, not those while the
+/// simulation is paused, see [simulation_integrating].
+pub fn update_settle_state(
+    stats: Res<SimulationStats>,
+    mut graph_changes: GraphChanges,
+    settings: Res<SimulationStatsSettings>,
+    mut state: ResMut<SettleState>,
+    mut settled_events: EventWriter<SimulationSettled>,
+    mut woke_events: EventWriter<SimulationWoke>,
+) {
+    let calm = stats.kinetic_energy < settings.energy_threshold && !graph_changes.any();
+
+    if state.settled {
+        if !calm {
+            *state = SettleState::default();
+            woke_events.send(SimulationWoke);
+        }
+    } else if calm {
+        if stats.is_changed() {
+            state.calm_ticks += 1;
+            if state.calm_ticks >= settings.settle_ticks {
+                state.settled = true;
+                settled_events.send(SimulationSettled);
+            }
+        }
+    } else if state.calm_ticks > 0 {
+        state.calm_ticks = 0;
+    }
+}
+
+pub fn toggle_stats_overlay(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut overlay_q: Query<&mut Node, With<StatsOverlay>>,
+) {
+    if !keyboard.just_pressed(KeyCode::F3) {
+        return;
+    }
+    for mut node in &mut overlay_q {
+        node.display = match node.display {
+            Display::None => Display::Flex,
+            _ => Display::None,
+        };
+    }
+}
+
+/// Show the [SimulationStats] in the overlay, if it is visible.
+pub fn update_stats_overlay(
+    stats: Res<SimulationStats>,
+    settle_state: Res<SettleState>,
+    overlay: Option<Single<&Node, With<StatsOverlay>>>,
+    text: Option<Single<&mut Text, With<StatsText>>>,
+) {
+    let (Some(overlay), Some(mut text)) = (overlay, text) else {
+        return;
+    };
+    if overlay.display == Display::None || !stats.is_changed() {
+        return;
+    }
+
+    let mut content = String::new();
+    let _ = writeln!(
+        content,
+        "tick: {}{}",
+        stats.tick,
+        if settle_state.settled {
+            " (settled)"
+        } else {
+            ""
+        }
+    );
+    let _ = writeln!(content, "kinetic energy: {:.1}", stats.kinetic_energy);
+    let _ = writeln!(
+        content,
+        "max displacement: {:.3} px",
+        stats.max_displacement
+    );
+    let _ = write!(content, "link stress: {:.2}", stats.link_stress);
+    if let Some(crossings) = stats.edge_crossings {
+        let _ = write!(content, "\nedge crossings: {}", crossings);
+    }
+    text.0 = content;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        count_crossings, segments_cross, simulation_integrating, update_settle_state,
+        update_simulation_stats, SettleState, SimulationSettled, SimulationStats,
+        SimulationStatsSettings, SimulationWoke,
+    };
+    use crate::force_directed_graph::{control::SimulationControl, ForceModel};
+    use bevy::{
+        app::{App, Update},
+        ecs::{entity::Entity, schedule::IntoSystemConfigs as _},
+        math::Vec2,
+    };
+
+    #[test]
+    fn test_count_crossings() {
+        let (a, b) = (Vec2::new(-1.0, 0.0), Vec2::new(1.0, 0.0));
+        let (c, d) = (Vec2::new(0.0, -1.0), Vec2::new(0.0, 1.0));
+        assert!(segments_cross((a, b), (c, d)));
+        assert!(!segments_cross(
+            (a, b),
+            (Vec2::new(2.0, -1.0), Vec2::new(2.0, 1.0))
+        ));
+        // Touching at an end point
+        assert!(!segments_cross((a, b), (b, Vec2::new(2.0, 1.0))));
+
+        let e = |i| Entity::from_raw(i);
+        assert_eq!(
+            count_crossings(&[(e(0), e(1), a, b), (e(2), e(3), c, d)]),
+            1
+        );
+        // Links sharing a node never cross
+        assert_eq!(
+            count_crossings(&[(e(0), e(1), a, b), (e(0), e(3), c, d)]),
+            0
+        );
+    }
+
+    #[test]
+    fn test_settle_only_while_integrating() {
+        let mut app = App::new();
+        app.insert_resource(SimulationStatsSettings {
+            edge_crossings: false,
+            energy_threshold: 100.0,
+            settle_ticks: 3,
+            pause_when_settled: false,
+        })
+        .init_resource::<SimulationControl>()
+        .insert_resource(ForceModel::Static)
+        .init_resource::<SimulationStats>()
+        .init_resource::<SettleState>()
+        .add_event::<SimulationSettled>()
+        .add_event::<SimulationWoke>()
+        .add_systems(
+            Update,
+            (
+                update_simulation_stats.run_if(simulation_integrating),
+                update_settle_state,
+            )
+                .chain(),
+        );
+        let settle_state = |app: &App| *app.world().resource::<SettleState>();
+        app.world_mut().resource_mut::<SimulationControl>().paused = true;
+
+        // The stats resource is new in the first tick
+        app.update();
+        for _ in 0..5 {
+            app.update();
+        }
+        assert_eq!(settle_state(&app).calm_ticks, 1);
+
+        app.world_mut().resource_mut::<SimulationControl>().paused = false;
+        for _ in 0..5 {
+            app.update();
+        }
+        assert_eq!(settle_state(&app).calm_ticks, 1);
+
+        *app.world_mut().resource_mut::<ForceModel>() = ForceModel::default();
+        app.update();
+        assert!(!settle_state(&app).settled);
+        app.update();
+        assert!(settle_state(&app).settled);
+    }
+}
@@ -0,0 +1,364 @@
+use std::{
+    f32::consts::{FRAC_PI_2, TAU},
+    fmt::Write as _,
+    fs,
+    path::PathBuf,
+};
+
+use bevy::{
+    app::{App, Plugin, Update},
+    color::{Alpha as _, Color, Srgba},
+    ecs::{
+        event::{Event, EventReader, EventWriter},
+        query::{With, Without},
+        system::{Query, Res},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    log::{error, info},
+    math::Vec2,
+    transform::components::Transform,
+};
+
+use super::{
+    common::{LinkStyle, NodeColor, NodeLabel, NodeLink, NodePhysics, NodeRadius},
+    hover::HoverHighlight,
+    node_shape::{clipped_endpoints, ArrowheadSettings, NodeShape},
+    utils::bounding_box,
+};
+
+/// Margin around the graph bounds in px.
+const MARGIN: f32 = 20.0;
+
+/// A node as it is drawn in the SVG.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgNode {
+    pub position: Vec2,
+    pub radius: f32,
+    /// Outline of the node. Images are drawn as squares in the node's color.
+    pub shape: NodeShape,
+    pub color: Color,
+    pub label: Option<String>,
+}
+
+/// A link as it is drawn in the SVG, already clipped to the shapes of its
+/// nodes (see [clipped_endpoints]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgLink {
+    pub source: Vec2,
+    pub target: Vec2,
+    pub thickness: f32,
+    pub color: Color,
+    /// Length of the arrowhead at the target end in px, if any
+    pub arrowhead: Option<f32>,
+}
+
+/// Render a standalone SVG document. The viewBox is fitted to the bounds of
+/// all nodes. Since the y axis of SVG points down, y coordinates are flipped.
+pub fn svg_document(nodes: &[SvgNode], links: &[SvgLink]) -> String {
+    let bounds = bounding_box(nodes.iter().flat_map(|node| {
+        let extent = |direction| node.shape.boundary_distance(node.radius, direction);
+        [
+            node.position - Vec2::new(extent(-Vec2::X), extent(-Vec2::Y)),
+            node.position + Vec2::new(extent(Vec2::X), extent(Vec2::Y)),
+        ]
+    }))
+    .unwrap_or_default();
+    let (min, max) = (bounds.min, bounds.max);
+    let min = min - Vec2::splat(MARGIN);
+    let size = max - min + Vec2::splat(MARGIN);
+
+    let mut svg = String::new();
+    // Writing into a String cannot fail
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+        min.x,
+        -max.y - MARGIN,
+        size.x,
+        size.y,
+        size.x,
+        size.y
+    );
+
+    for link in links {
+        let (color, opacity) = svg_color(link.color);
+        let _ = writeln!(
+            svg,
+            r#"  <line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-opacity="{}" stroke-width="{}"/>"#,
+            link.source.x,
+            -link.source.y,
+            link.target.x,
+            -link.target.y,
+            color,
+            opacity,
+            link.thickness
+        );
+        // Same geometry as the arrowhead mesh, see [super::node_shape::setup_arrowheads]
+        let Some(size) = link
+            .arrowhead
+            .filter(|size| link.source.distance(link.target) >= *size)
+        else {
+            continue;
+        };
+        let direction = (link.target - link.source).normalize_or_zero();
+        let base = link.target - direction * size;
+        let side = direction.perp() * size / 2.0;
+        let _ = writeln!(
+            svg,
+            r#"  <polygon points="{}" fill="{}" fill-opacity="{}"/>"#,
+            svg_points([link.target, base + side, base - side]),
+            color,
+            opacity
+        );
+    }
+
+    for node in nodes {
+        let (color, opacity) = svg_color(node.color);
+        let (x, y, radius) = (node.position.x, -node.position.y, node.radius);
+        let fill = format!(r#"fill="{}" fill-opacity="{}""#, color, opacity);
+        let _ = match node.shape {
+            NodeShape::Circle => writeln!(
+                svg,
+                r#"  <circle cx="{}" cy="{}" r="{}" {}/>"#,
+                x, y, radius, fill
+            ),
+            NodeShape::Rectangle { aspect_ratio } => writeln!(
+                svg,
+                r#"  <rect x="{}" y="{}" width="{}" height="{}" {}/>"#,
+                x - radius * aspect_ratio,
+                y - radius,
+                2.0 * radius * aspect_ratio,
+                2.0 * radius,
+                fill
+            ),
+            NodeShape::RoundedRectangle {
+                aspect_ratio,
+                corner_radius,
+            } => writeln!(
+                svg,
+                r#"  <rect x="{}" y="{}" width="{}" height="{}" rx="{}" {}/>"#,
+                x - radius * aspect_ratio,
+                y - radius,
+                2.0 * radius * aspect_ratio,
+                2.0 * radius,
+                (radius * corner_radius).clamp(0.0, radius * aspect_ratio.min(1.0)),
+                fill
+            ),
+            NodeShape::Polygon { sides } => {
+                // Vertex at the top, like [bevy::math::primitives::RegularPolygon]
+                let sides = sides.max(3);
+                let vertices = (0..sides).map(|i| {
+                    let angle = FRAC_PI_2 + TAU * i as f32 / sides as f32;
+                    node.position + Vec2::from_angle(angle) * radius
+                });
+                writeln!(
+                    svg,
+                    r#"  <polygon points="{}" {}/>"#,
+                    svg_points(vertices),
+                    fill
+                )
+            }
+            NodeShape::Image(_) => writeln!(
+                svg,
+                r#"  <rect x="{}" y="{}" width="{}" height="{}" {}/>"#,
+                x - radius,
+                y - radius,
+                2.0 * radius,
+                2.0 * radius,
+                fill
+            ),
+        };
+        if let Some(label) = &node.label {
+            let _ = writeln!(
+                svg,
+                r#"  <text x="{}" y="{}" font-family="sans-serif" font-size="10" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                x,
+                y,
+                escape_xml(label)
+            );
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Points of a `<polygon>`, with flipped y coordinates.
+fn svg_points(points: impl IntoIterator<Item = Vec2>) -> String {
+    points
+        .into_iter()
+        .map(|point| format!("{},{}", point.x, -point.y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Hex color and opacity.
+fn svg_color(color: Color) -> (String, f32) {
+    let srgba = Srgba::from(color);
+    (srgba.with_alpha(1.0).to_hex(), srgba.alpha)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Event to export the current drawing as SVG file.
+#[derive(Event, Debug, Clone)]
+pub struct ExportSvg(pub PathBuf);
+
+/// Plugin for exporting the graph as SVG on an [ExportSvg] event or via
+/// keyboard (`Ctrl+E`, writes to `path`). Also works without rendering, e.g. in
+/// headless mode: colors default to white if there are no materials.
+pub struct SvgExportPlugin {
+    pub path: PathBuf,
+}
+
+impl Default for SvgExportPlugin {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("graph.svg"),
+        }
+    }
+}
+
+impl Plugin for SvgExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ExportSvg>()
+            .add_systems(Update, (svg_keyboard_input(self.path.clone()), export_svg));
+    }
+}
+
+/// Send an [ExportSvg] event on `Ctrl+E`.
+pub fn svg_keyboard_input(
+    path: PathBuf,
+) -> impl Fn(Res<'_, ButtonInput<KeyCode>>, EventWriter<'_, ExportSvg>) {
+    move |keyboard: Res<ButtonInput<KeyCode>>, mut export_events: EventWriter<ExportSvg>| {
+        if keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+            && keyboard.just_pressed(KeyCode::KeyE)
+        {
+            export_events.send(ExportSvg(path.clone()));
+        }
+    }
+}
+
+/// Write the SVG file on an [ExportSvg] event. Links end at the shapes of
+/// their nodes and get arrowheads if they are enabled, see
+/// [ArrowheadSettings]. Colors are exported without hover highlighting: links
+/// in their [LinkStyle] color, nodes with their original alpha.
+pub fn export_svg(
+    mut export_events: EventReader<ExportSvg>,
+    nodes_q: Query<
+        (
+            &Transform,
+            Option<&NodeRadius>,
+            Option<&NodeShape>,
+            Option<&NodeLabel>,
+            Option<&NodeColor>,
+            Option<&HoverHighlight>,
+        ),
+        With<NodePhysics>,
+    >,
+    links_q: Query<(&NodeLink, Option<&LinkStyle>), Without<NodePhysics>>,
+    arrowhead_settings: Option<Res<ArrowheadSettings>>,
+) {
+    for ExportSvg(path) in export_events.read() {
+        let nodes = nodes_q
+            .iter()
+            .map(
+                |(transform, radius, shape, label, color, highlight)| SvgNode {
+                    position: transform.translation.truncate(),
+                    radius: radius.copied().unwrap_or_default().0,
+                    shape: shape.cloned().unwrap_or_default(),
+                    color: color.map_or(Color::WHITE, |color| {
+                        highlight.map_or(color.0, |highlight| {
+                            color.0.with_alpha(highlight.original_alpha)
+                        })
+                    }),
+                    label: label.map(|label| label.0.clone()),
+                },
+            )
+            .collect::<Vec<_>>();
+        let links = links_q
+            .iter()
+            .filter_map(|(link, style)| {
+                let (source, source_radius, source_shape, ..) = nodes_q.get(link.source).ok()?;
+                let (target, target_radius, target_shape, ..) = nodes_q.get(link.target).ok()?;
+                let (source, target) = clipped_endpoints(
+                    source.translation.truncate(),
+                    (source_shape, source_radius),
+                    target.translation.truncate(),
+                    (target_shape, target_radius),
+                );
+                let style = style.copied().unwrap_or_default();
+                Some(SvgLink {
+                    source,
+                    target,
+                    thickness: style.thickness,
+                    color: style.color,
+                    arrowhead: arrowhead_settings
+                        .as_deref()
+                        .filter(|settings| settings.enabled)
+                        .map(|settings| settings.size + style.thickness),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        match fs::write(path, svg_document(&nodes, &links)) {
+            Ok(()) => info!("Exported graph to {}", path.display()),
+            Err(e) => error!("Cannot export graph to {}: {}", path.display(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_svg_document() {
+        let nodes = [
+            SvgNode {
+                position: Vec2::new(0.0, 0.0),
+                radius: 10.0,
+                shape: NodeShape::Circle,
+                color: Color::srgb(1.0, 0.0, 0.0),
+                label: Some("a<b".to_string()),
+            },
+            SvgNode {
+                position: Vec2::new(100.0, 50.0),
+                radius: 10.0,
+                shape: NodeShape::Polygon { sides: 4 },
+                color: Color::srgb(0.0, 0.0, 1.0),
+                label: None,
+            },
+        ];
+        let links = [SvgLink {
+            source: nodes[0].position,
+            target: nodes[1].position,
+            thickness: 2.5,
+            color: Color::srgba(1.0, 1.0, 1.0, 0.5),
+            arrowhead: Some(10.0),
+        }];
+
+        let svg = svg_document(&nodes, &links);
+
+        assert!(svg.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-30 -80 160 110" width="160" height="110">"#
+        ));
+        assert!(svg.contains(
+            r##"<line x1="0" y1="-0" x2="100" y2="-50" stroke="#FFFFFF" stroke-opacity="0.5" stroke-width="2.5"/>"##
+        ));
+        assert!(
+            svg.contains(r##"<circle cx="0" cy="-0" r="10" fill="#FF0000" fill-opacity="1"/>"##)
+        );
+        assert!(svg.contains(r##"<polygon points="100,-50 "##));
+        assert!(svg.contains(
+            r##"<polygon points="100,-60 90,-50 100,-40 110,-50" fill="#0000FF" fill-opacity="1"/>"##
+        ));
+        assert!(svg.contains(">a&lt;b</text>"));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+}
@@ -0,0 +1,251 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        schedule::IntoSystemConfigs as _,
+        system::{Commands, Query, Res, Resource},
+    },
+    math::{
+        curve::{Curve as _, EaseFunction, EasingCurve},
+        Vec2,
+    },
+    render::camera::OrthographicProjection,
+    time::Time,
+    transform::{components::Transform, TransformSystem},
+};
+
+use super::common::{NodePhysics, Pinned};
+
+/// Component for nodes which are animated towards a target position. While a
+/// node is animated, verlet integration is suspended for it (see
+/// [crate::force_directed_graph::verlet::apply_velocity]). Afterwards, the node
+/// is handed back to the simulation with `velocity`.
+#[derive(Component, Debug, Clone)]
+pub struct Tween {
+    pub start: Vec2,
+    pub target: Vec2,
+    /// Velocity at the end of the animation in px per tick, zero by default
+    pub velocity: Vec2,
+    /// Time since the start of the animation in s
+    pub elapsed: f32,
+    /// Duration of the animation in s
+    pub duration: f32,
+    pub easing: EaseFunction,
+}
+
+impl Tween {
+    pub fn new(start: Vec2, target: Vec2, duration: f32, easing: EaseFunction) -> Self {
+        Self {
+            start,
+            target,
+            velocity: Vec2::ZERO,
+            elapsed: 0.0,
+            duration,
+            easing,
+        }
+    }
+
+    /// Progress of the animation from 0.0 to 1.0 (not eased).
+    pub fn progress(&self) -> f32 {
+        if self.duration > 0.0 {
+            self.elapsed / self.duration
+        } else {
+            1.0
+        }
+    }
+
+    /// Position at the current point of time of the animation.
+    pub fn position(&self) -> Vec2 {
+        EasingCurve::new(self.start, self.target, self.easing).sample_clamped(self.progress())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+/// Component for a 2d camera which is animated towards a target position and
+/// zoom (scale of the orthographic projection).
+#[derive(Component, Debug, Clone)]
+pub struct CameraTween {
+    pub translation: Tween,
+    pub start_scale: f32,
+    pub target_scale: f32,
+}
+
+impl CameraTween {
+    /// Scale at the current point of time of the animation.
+    pub fn scale(&self) -> f32 {
+        EasingCurve::new(self.start_scale, self.target_scale, self.translation.easing)
+            .sample_clamped(self.translation.progress())
+    }
+}
+
+/// How nodes are animated when their positions are replaced, e.g. by a layout.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct TweenSettings {
+    /// Duration of the animation in s. If zero, nodes jump to their new position.
+    pub duration: f32,
+    pub easing: EaseFunction,
+}
+
+pub struct TweenPlugin {
+    pub duration: f32,
+    pub easing: EaseFunction,
+}
+
+impl Default for TweenPlugin {
+    fn default() -> Self {
+        Self {
+            duration: 0.75,
+            easing: EaseFunction::CubicInOut,
+        }
+    }
+}
+
+impl Plugin for TweenPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TweenSettings {
+            duration: self.duration,
+            easing: self.easing,
+        })
+        // Runs after all forces, so animated nodes are not moved by them
+        .add_systems(
+            PostUpdate,
+            (animate_tweens, animate_camera_tweens).before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Move a node to a new position. If [TweenSettings] are given, the node is
+/// animated, otherwise it jumps to the position immediately. Either way, the
+/// node comes to rest at the new position.
+pub fn move_node(
+    commands: &mut Commands,
+    entity: Entity,
+    transform: &mut Transform,
+    node_physics: &mut NodePhysics,
+    position: Vec2,
+    tween_settings: Option<&TweenSettings>,
+) {
+    move_node_with_velocity(
+        commands,
+        entity,
+        transform,
+        node_physics,
+        position,
+        Vec2::ZERO,
+        tween_settings,
+    );
+}
+
+/// Like [move_node], but the node keeps moving with `velocity` (in px per
+/// tick) at the new position.
+pub fn move_node_with_velocity(
+    commands: &mut Commands,
+    entity: Entity,
+    transform: &mut Transform,
+    node_physics: &mut NodePhysics,
+    position: Vec2,
+    velocity: Vec2,
+    tween_settings: Option<&TweenSettings>,
+) {
+    match tween_settings {
+        Some(settings) if settings.duration > 0.0 => {
+            commands.entity(entity).insert(Tween {
+                velocity,
+                ..Tween::new(
+                    transform.translation.truncate(),
+                    position,
+                    settings.duration,
+                    settings.easing,
+                )
+            });
+        }
+        _ => {
+            transform.translation = position.extend(transform.translation.z);
+            node_physics.previous_position = position - velocity;
+        }
+    }
+}
+
+/// Advance all animations. Finished animations are removed, handing the node
+/// back to the simulation with the velocity of the [Tween]. Pinned nodes are
+/// pinned to the animated position.
+pub fn animate_tweens(
+    mut tweens_q: Query<(
+        Entity,
+        &mut Tween,
+        &mut Transform,
+        &mut NodePhysics,
+        Option<&mut Pinned>,
+    )>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut tween, mut transform, mut node_physics, pinned) in &mut tweens_q {
+        tween.elapsed += time.delta_secs();
+
+        let position = tween.position();
+        transform.translation = position.extend(transform.translation.z);
+        node_physics.previous_position = position;
+        if let Some(mut pinned) = pinned {
+            pinned.position = position;
+        }
+
+        if tween.is_finished() {
+            node_physics.previous_position = position - tween.velocity;
+            commands.entity(entity).remove::<Tween>();
+        }
+    }
+}
+
+/// Advance all camera animations. Finished animations are removed.
+pub fn animate_camera_tweens(
+    mut tweens_q: Query<(
+        Entity,
+        &mut CameraTween,
+        &mut Transform,
+        &mut OrthographicProjection,
+    )>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut tween, mut transform, mut projection) in &mut tweens_q {
+        tween.translation.elapsed += time.delta_secs();
+
+        let position = tween.translation.position();
+        transform.translation = position.extend(transform.translation.z);
+        projection.scale = tween.scale();
+
+        if tween.translation.is_finished() {
+            commands.entity(entity).remove::<CameraTween>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tween;
+    use bevy::math::{curve::EaseFunction, Vec2};
+
+    #[test]
+    fn test_tween_position() {
+        let mut tween = Tween::new(
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 20.0),
+            2.0,
+            EaseFunction::Linear,
+        );
+        assert_eq!(tween.position(), Vec2::new(0.0, 0.0));
+
+        tween.elapsed = 1.0;
+        assert_eq!(tween.position(), Vec2::new(5.0, 10.0));
+        assert!(!tween.is_finished());
+
+        tween.elapsed = 3.0;
+        assert_eq!(tween.position(), Vec2::new(10.0, 20.0));
+        assert!(tween.is_finished());
+    }
+}
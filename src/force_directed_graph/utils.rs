@@ -1,4 +1,4 @@
-use bevy::math::{Vec2, Vec3};
+use bevy::math::{Rect, Vec2, Vec3};
 use rand::rngs::SmallRng;
 use rand::{Rng as _, SeedableRng as _};
 
@@ -110,6 +110,19 @@ impl ClampF32Range for Vec3 {
     }
 }
 
+/// Smallest rectangle containing all positions, `None` if there are none.
+pub fn bounding_box(positions: impl IntoIterator<Item = Vec2>) -> Option<Rect> {
+    positions
+        .into_iter()
+        .fold(None, |bounds: Option<Rect>, position| {
+            Some(
+                bounds.map_or(Rect::from_corners(position, position), |bounds| {
+                    bounds.union_point(position)
+                }),
+            )
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -197,4 +210,13 @@ mod tests {
     fn test_clamp_finite_or_infinite() {
         assert_eq!(f32::NAN.clamp_finite_or(0.0, 1.0, 10.0), 10.0);
     }
+
+    #[test]
+    fn test_bounding_box() {
+        assert_eq!(bounding_box([]), None);
+        assert_eq!(
+            bounding_box([Vec2::new(1.0, -2.0), Vec2::new(-3.0, 4.0), Vec2::ZERO]),
+            Some(Rect::new(-3.0, -2.0, 1.0, 4.0))
+        );
+    }
 }
@@ -1,10 +1,18 @@
+use super::control::SimulationControl;
+use super::tween::Tween;
 use super::utils::{ClampF32Range, FiniteOr as _};
-use crate::force_directed_graph::common::{MouseLocked, NodePhysics};
+use super::SimulationSet;
+use crate::force_directed_graph::common::{ForceAccumulator, MouseLocked, NodePhysics};
 use bevy::{
-    app::{App, Plugin, PreUpdate},
-    ecs::system::{Query, Res, ResMut, Resource},
+    app::{App, Plugin, PreUpdate, Update},
+    ecs::{
+        query::Without,
+        schedule::IntoSystemConfigs as _,
+        system::{Query, Res, ResMut, Resource},
+    },
     time::Time,
     transform::components::Transform,
+    utils::HashMap,
 };
 use core::{f32, panic};
 use std::collections::VecDeque;
@@ -77,6 +85,17 @@ impl VerletRes {
     }
 }
 
+/// Factor per force (by its label in the [ForceAccumulator], e.g. `"link"`)
+/// by which [apply_forces] scales it. Forces without an entry are not scaled.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ForceScales(pub HashMap<&'static str, f32>);
+
+impl ForceScales {
+    pub fn get(&self, label: &str) -> f32 {
+        self.0.get(label).copied().unwrap_or(1.0)
+    }
+}
+
 pub struct VerletPlugin {
     pub velocity_decay: f32,
 }
@@ -85,7 +104,17 @@ impl Plugin for VerletPlugin {
     fn build(&self, app: &mut App) {
         // TODO add bevy::Time if not added already?
         app.insert_resource(VerletRes::default())
-            .add_systems(PreUpdate, (apply_velocity(self.velocity_decay),));
+            .init_resource::<ForceScales>()
+            .add_systems(
+                PreUpdate,
+                apply_velocity(self.velocity_decay).in_set(SimulationSet::Integration),
+            )
+            .add_systems(
+                Update,
+                apply_forces
+                    .after(SimulationSet::Forces)
+                    .before(SimulationSet::Constraints),
+            );
     }
 }
 
@@ -93,9 +122,8 @@ impl Plugin for VerletPlugin {
 /// this function calculates the new position using Verlet integration.
 ///
 /// Usually, the Verlet integration considers the acceleration (based on some
-/// forces and a mass of 1). However, here the forces change the current
-/// position directly, so we do not need to consider (i.e. save and calculate)
-/// the acceleration.
+/// forces and a mass of 1) in the same step. Here, the forces are applied
+/// separately by [apply_forces], after they have been accumulated.
 ///
 /// Inputs:
 ///  - node position: result of last Verlet integration step
@@ -104,23 +132,36 @@ impl Plugin for VerletPlugin {
 /// Outputs:
 ///  - node position: new position of the node
 ///  - previous position: result of the last Verlet integration step
+///
+/// Nodes which are currently animated (see [Tween]) are skipped. The time
+/// step is multiplied by [SimulationControl::time_scale].
 pub fn apply_velocity(
     velocity_decay: f32,
 ) -> impl FnMut(
-    Query<'_, '_, (&mut Transform, &mut NodePhysics, Option<&mut MouseLocked>)>,
+    Query<'_, '_, (&mut Transform, &mut NodePhysics, Option<&mut MouseLocked>), Without<Tween>>,
     Res<'_, Time>,
     ResMut<'_, VerletRes>,
+    Option<Res<'_, SimulationControl>>,
 ) {
-    move |mut nodes_q: Query<(&mut Transform, &mut NodePhysics, Option<&mut MouseLocked>)>,
+    move |mut nodes_q: Query<
+        (&mut Transform, &mut NodePhysics, Option<&mut MouseLocked>),
+        Without<Tween>,
+    >,
           time: Res<Time>,
-          mut verlet: ResMut<VerletRes>| {
-        // First, update time steps
-        verlet.update(if time.delta_secs() != 0.0 {
-            // TODO
-            time.delta_secs()
-        } else {
-            0.01
-        });
+          mut verlet: ResMut<VerletRes>,
+          control: Option<Res<SimulationControl>>| {
+        // First, update time steps. Forces scale with them, so the time scale
+        // of the simulation only needs to be applied here.
+        let time_scale = control.map_or(1.0, |control| control.time_scale);
+        verlet.update(
+            time_scale
+                * if time.delta_secs() != 0.0 {
+                    // TODO
+                    time.delta_secs()
+                } else {
+                    0.01
+                },
+        );
 
         // Do verlet integration
         nodes_q
@@ -152,3 +193,96 @@ pub fn apply_velocity(
             });
     }
 }
+
+/// Move every node by the sum of the forces in its [ForceAccumulator] (mass
+/// of 1, see [ForceScales]) and clear it. Since all forces only add into the
+/// accumulator, their order does not matter.
+///
+/// Nodes which are mouse locked or animated (see [Tween]) are not moved.
+pub fn apply_forces(
+    mut nodes_q: Query<(
+        &mut Transform,
+        &mut ForceAccumulator,
+        Option<&MouseLocked>,
+        Option<&Tween>,
+    )>,
+    verlet: Res<VerletRes>,
+    scales: Res<ForceScales>,
+) {
+    for (mut transform, mut accumulator, mouse_locked, tween) in &mut nodes_q {
+        // Don't trigger change detection while the simulation is paused
+        if accumulator.forces.is_empty() {
+            continue;
+        }
+        if mouse_locked.is_none() && tween.is_none() {
+            let force = accumulator.total(|label| scales.get(label));
+            let displacement = (force * verlet.delta_secs_squared()).clamp_f32_range();
+            transform.translation =
+                (transform.translation + displacement.extend(0.0)).clamp_f32_range();
+            #[cfg(debug_assertions)]
+            assert!(transform.is_finite(), "Not finite: {:?}", transform);
+        }
+        accumulator.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_forces, ForceScales, VerletRes};
+    use crate::force_directed_graph::common::{ForceAccumulator, MouseLocked, NodePhysics};
+    use bevy::{
+        app::{App, Update},
+        math::{Vec2, Vec3},
+        transform::components::Transform,
+    };
+
+    #[test]
+    fn test_apply_forces() {
+        let mut app = App::new();
+        let mut verlet = VerletRes::default();
+        verlet.update(0.5);
+        let mut scales = ForceScales::default();
+        scales.0.insert("half", 0.5);
+        app.insert_resource(verlet)
+            .insert_resource(scales)
+            .add_systems(Update, apply_forces);
+
+        let transform = Transform::from_translation(Vec3::new(1.0, 2.0, 3.0));
+        let node = app
+            .world_mut()
+            .spawn((transform, NodePhysics::from_transform(transform)))
+            .id();
+        let mouse_locked = app
+            .world_mut()
+            .spawn((
+                transform,
+                NodePhysics::from_transform(transform),
+                MouseLocked {
+                    velocity: Vec2::ZERO,
+                },
+            ))
+            .id();
+        for entity in [node, mouse_locked] {
+            let mut accumulator = app.world_mut().get_mut::<ForceAccumulator>(entity).unwrap();
+            accumulator.add("full", Vec2::new(4.0, 0.0));
+            accumulator.add("half", Vec2::new(0.0, 8.0));
+            accumulator.add("full", Vec2::new(4.0, 0.0));
+        }
+
+        app.update();
+
+        // (8, 0) + 0.5 * (0, 8), multiplied with 0.5^2
+        assert_eq!(
+            app.world().get::<Transform>(node).unwrap().translation,
+            Vec3::new(3.0, 3.0, 3.0)
+        );
+        assert_eq!(
+            *app.world().get::<Transform>(mouse_locked).unwrap(),
+            transform
+        );
+        for entity in [node, mouse_locked] {
+            let accumulator = app.world().get::<ForceAccumulator>(entity).unwrap();
+            assert!(accumulator.forces.is_empty());
+        }
+    }
+}